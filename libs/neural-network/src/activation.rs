@@ -0,0 +1,151 @@
+use crate::*;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(
+    IntoStaticStr,
+    EnumIter,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Activation {
+    #[default]
+    ReLU,
+    /// ReLU that lets a scaled fraction of negative inputs through.
+    /// (slope)
+    LeakyReLU(f32),
+    Sigmoid,
+    Tanh,
+    Identity,
+    Softsign,
+}
+
+impl Activation {
+    pub fn apply<S: Scalar>(&self, x: S) -> S {
+        match self {
+            Self::ReLU => x.max(S::zero()),
+            Self::LeakyReLU(slope) => {
+                if x > S::zero() {
+                    x
+                } else {
+                    scalar::<S>(*slope) * x
+                }
+            }
+            Self::Sigmoid => S::one() / (S::one() + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Identity => x,
+            Self::Softsign => x / (S::one() + x.abs()),
+        }
+    }
+
+    /// Slope of `apply` at `x`; ReLU's kink counts as flat.
+    pub fn derivative<S: Scalar>(&self, x: S) -> S {
+        match self {
+            Self::ReLU => {
+                if x > S::zero() {
+                    S::one()
+                } else {
+                    S::zero()
+                }
+            }
+            Self::LeakyReLU(slope) => {
+                if x > S::zero() {
+                    S::one()
+                } else {
+                    scalar(*slope)
+                }
+            }
+            Self::Sigmoid => {
+                let y = self.apply(x);
+
+                y * (S::one() - y)
+            }
+            Self::Tanh => S::one() - x.tanh().powi(2),
+            Self::Identity => S::one(),
+            Self::Softsign => S::one() / (S::one() + x.abs()).powi(2),
+        }
+    }
+
+    pub(crate) fn apply_vector<S: Scalar>(&self, input: &DVector<S>) -> DVector<S> {
+        input.map(|x| self.apply(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use strum::IntoEnumIterator;
+
+    fn actual(activation: Activation) -> Vec<f32> {
+        [-2.0, -0.5, 0.0, 0.5, 2.0]
+            .into_iter()
+            .map(|x| activation.apply(x))
+            .collect()
+    }
+
+    #[test]
+    fn test_relu() {
+        assert_relative_eq!(
+            actual(Activation::ReLU).as_slice(),
+            [0.0, 0.0, 0.0, 0.5, 2.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_leaky_relu() {
+        assert_relative_eq!(
+            actual(Activation::LeakyReLU(0.1)).as_slice(),
+            [-0.2, -0.05, 0.0, 0.5, 2.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_sigmoid() {
+        assert_relative_eq!(
+            actual(Activation::Sigmoid).as_slice(),
+            [0.11920292, 0.37754068, 0.5, 0.62245935, 0.8807971].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_tanh() {
+        assert_relative_eq!(
+            actual(Activation::Tanh).as_slice(),
+            [-0.9640276, -0.46211717, 0.0, 0.46211717, 0.9640276].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_identity() {
+        assert_relative_eq!(
+            actual(Activation::Identity).as_slice(),
+            [-2.0, -0.5, 0.0, 0.5, 2.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_softsign() {
+        assert_relative_eq!(
+            actual(Activation::Softsign).as_slice(),
+            [-0.6666667, -0.33333334, 0.0, 0.33333334, 0.6666667].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_derivative() {
+        let h = 1e-3;
+
+        for activation in Activation::iter() {
+            for x in [-2.0, -0.5, 0.5, 2.0] {
+                let expected = (activation.apply(x + h) - activation.apply(x - h)) / (2.0 * h);
+
+                assert_relative_eq!(activation.derivative(x), expected, epsilon = 1e-2);
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::*;
+
+/// Common interface of the brain implementations, so that callers can pick
+/// one at runtime and still evolve it through a flat `Chromosome`.
+pub trait Controller: Send + Sync {
+    /// Maps `input` to `out`, advancing any internal state.
+    fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]);
+
+    /// Like `propagate_into`, but allocates and keeps every neuron's
+    /// intermediate values.
+    fn propagate_traced(&mut self, input: &[f32]) -> Trace;
+
+    fn reset_state(&mut self);
+
+    /// Reinforces whatever a plastic controller just did; ignored by the
+    /// rest.
+    fn reward(&mut self, _reward: f32) {}
+
+    /// Every evolvable parameter, in the order the matching `from_weights`
+    /// expects them.
+    fn weights(&self) -> Box<dyn Iterator<Item = f32> + '_>;
+
+    /// Lets callers reach `Network`-only features such as `to_dot`.
+    fn as_network(&self) -> Option<&Network> {
+        None
+    }
+}
+
+impl Controller for Network {
+    fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        Network::propagate_into(self, input, scratch, out)
+    }
+
+    fn propagate_traced(&mut self, input: &[f32]) -> Trace {
+        Network::propagate_traced(self, DVector::from_column_slice(input))
+    }
+
+    fn reset_state(&mut self) {
+        Network::reset_state(self)
+    }
+
+    fn reward(&mut self, reward: f32) {
+        Network::reward(self, reward)
+    }
+
+    fn weights(&self) -> Box<dyn Iterator<Item = f32> + '_> {
+        Box::new(Network::weights(self))
+    }
+
+    fn as_network(&self) -> Option<&Network> {
+        Some(self)
+    }
+}
@@ -0,0 +1,357 @@
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CtrnnTopology {
+    pub inputs: usize,
+    pub neurons: usize,
+    /// The last `outputs` neurons are read out.
+    pub outputs: usize,
+    /// Applied to `state + bias` of every neuron.
+    pub activation: Activation,
+    /// Euler integration step. Time constants below it are clamped to it.
+    pub dt: f32,
+}
+
+/// Continuous-time recurrent neural network: every neuron is connected to
+/// every other one and integrates
+/// `tau * dy/dt = -y + W * act(y + bias) + W_in * input`.
+pub struct Ctrnn {
+    topology: CtrnnTopology,
+    biases: DVector<f32>,
+    time_constants: DVector<f32>,
+    input_weights: DMatrix<f32>,
+    weights: DMatrix<f32>,
+    state: DVector<f32>,
+}
+
+impl Ctrnn {
+    pub fn random(topology: CtrnnTopology, rng: &mut dyn RngCore) -> Self {
+        Self::check(&topology).unwrap();
+
+        let CtrnnTopology {
+            inputs,
+            neurons,
+            dt,
+            ..
+        } = topology;
+
+        Self {
+            topology,
+            biases: DVector::from_fn(neurons, |_, _| rng.gen_range(-1.0..1.0)),
+            time_constants: DVector::from_fn(neurons, |_, _| rng.gen_range(1.0..10.0) * dt),
+            input_weights: DMatrix::from_fn(neurons, inputs, |_, _| rng.gen_range(-1.0..1.0)),
+            weights: DMatrix::from_fn(neurons, neurons, |_, _| rng.gen_range(-1.0..1.0)),
+            state: DVector::zeros(neurons),
+        }
+    }
+
+    /// Biases, then time constants, then input weights, then recurrent
+    /// weights.
+    pub fn weights(&self) -> impl Iterator<Item = f32> + '_ {
+        self.biases
+            .iter()
+            .chain(self.time_constants.iter())
+            .chain(self.input_weights.iter())
+            .chain(self.weights.iter())
+            .copied()
+    }
+
+    pub fn from_weights(topology: CtrnnTopology, weights: impl IntoIterator<Item = f32>) -> Self {
+        Self::try_from_weights(topology, weights).unwrap()
+    }
+
+    pub fn try_from_weights(
+        topology: CtrnnTopology,
+        weights: impl IntoIterator<Item = f32>,
+    ) -> Result<Self, NetworkError> {
+        Self::check(&topology)?;
+
+        let CtrnnTopology {
+            inputs, neurons, ..
+        } = topology;
+
+        let expected = inputs
+            .checked_add(neurons)
+            .and_then(|len| len.checked_add(2))
+            .and_then(|len| len.checked_mul(neurons))
+            .ok_or(NetworkError::TooLarge)?;
+        let weights: Vec<f32> = weights.into_iter().take(expected + 1).collect();
+
+        if weights.len() < expected {
+            return Err(NetworkError::TooFewWeights(expected, weights.len()));
+        }
+
+        if weights.len() > expected {
+            return Err(NetworkError::LeftoverWeights(expected));
+        }
+
+        let (biases, rest) = weights.split_at(neurons);
+        let (time_constants, rest) = rest.split_at(neurons);
+        let (input_weights, rest) = rest.split_at(neurons * inputs);
+
+        Ok(Self {
+            topology,
+            biases: DVector::from_column_slice(biases),
+            time_constants: DVector::from_column_slice(time_constants),
+            input_weights: DMatrix::from_column_slice(neurons, inputs, input_weights),
+            weights: DMatrix::from_column_slice(neurons, neurons, rest),
+            state: DVector::zeros(neurons),
+        })
+    }
+
+    pub fn topology(&self) -> CtrnnTopology {
+        self.topology
+    }
+
+    pub fn propagate(&mut self, input: DVector<f32>) -> DVector<f32> {
+        let mut out = DVector::zeros(self.topology.outputs);
+
+        self.propagate_into(
+            input.as_slice(),
+            &mut Scratch::default(),
+            out.as_mut_slice(),
+        );
+
+        out
+    }
+
+    /// Advances the network by one `dt` and writes the output neurons'
+    /// activations to `out`.
+    pub fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        let CtrnnTopology {
+            inputs,
+            neurons,
+            outputs,
+            activation,
+            dt,
+        } = self.topology;
+
+        assert_eq!(input.len(), inputs);
+        assert_eq!(out.len(), outputs);
+
+        let (front, back) = scratch.buffers(neurons);
+        let (front, back) = (&mut front[..neurons], &mut back[..neurons]);
+
+        for (i, fired) in front.iter_mut().enumerate() {
+            *fired = activation.apply(self.state[i] + self.biases[i]);
+        }
+
+        let mut target = DVectorViewMut::from_slice(back, neurons);
+
+        target.gemv(
+            1.0,
+            &self.weights,
+            &DVectorView::from_slice(front, neurons),
+            0.0,
+        );
+        target.gemv(
+            1.0,
+            &self.input_weights,
+            &DVectorView::from_slice(input, inputs),
+            1.0,
+        );
+
+        for (i, target) in target.iter().enumerate() {
+            let tau = self.time_constants[i].max(dt);
+
+            self.state[i] += dt / tau * (target - self.state[i]);
+        }
+
+        for (out, i) in out.iter_mut().zip(neurons - outputs..neurons) {
+            *out = activation.apply(self.state[i] + self.biases[i]);
+        }
+    }
+
+    /// Like `propagate`; the trace has a single layer holding every neuron.
+    pub fn propagate_traced(&mut self, input: DVector<f32>) -> Trace {
+        let output = self.propagate(input);
+        let pre_activation = (&self.state + &self.biases).data.into();
+
+        Trace {
+            layers: vec![LayerTrace::new(pre_activation, self.topology.activation)],
+            output: output.data.into(),
+        }
+    }
+
+    pub fn reset_state(&mut self) {
+        self.state.fill(0.0);
+    }
+
+    fn check(topology: &CtrnnTopology) -> Result<(), NetworkError> {
+        if topology.outputs > topology.neurons {
+            return Err(NetworkError::InvalidTopology(format!(
+                "{} outputs out of {} neurons",
+                topology.outputs, topology.neurons
+            )));
+        }
+
+        if topology.dt.is_nan() || topology.dt <= 0.0 {
+            return Err(NetworkError::InvalidTopology(format!(
+                "dt must be positive, got {}",
+                topology.dt
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Controller for Ctrnn {
+    fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        Ctrnn::propagate_into(self, input, scratch, out)
+    }
+
+    fn propagate_traced(&mut self, input: &[f32]) -> Trace {
+        Ctrnn::propagate_traced(self, DVector::from_column_slice(input))
+    }
+
+    fn reset_state(&mut self) {
+        Ctrnn::reset_state(self)
+    }
+
+    fn weights(&self) -> Box<dyn Iterator<Item = f32> + '_> {
+        Box::new(Ctrnn::weights(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const TOPOLOGY: CtrnnTopology = CtrnnTopology {
+        inputs: 3,
+        neurons: 4,
+        outputs: 2,
+        activation: Activation::Tanh,
+        dt: 0.1,
+    };
+
+    /// One neuron that integrates its input with no self-connection.
+    fn integrator(time_constant: f32) -> Ctrnn {
+        let topology = CtrnnTopology {
+            inputs: 1,
+            neurons: 1,
+            outputs: 1,
+            activation: Activation::Identity,
+            dt: 0.5,
+        };
+
+        Ctrnn::from_weights(topology, vec![0.0, time_constant, 1.0, 0.0])
+    }
+
+    #[test]
+    fn test_weights() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network = Ctrnn::random(TOPOLOGY, &mut rng);
+        let weights: Vec<_> = network.weights().collect();
+
+        let actual = Ctrnn::from_weights(TOPOLOGY, weights.clone());
+        let actual: Vec<_> = actual.weights().collect();
+
+        assert_eq!(weights.len(), 4 * (2 + 3 + 4));
+        assert_relative_eq!(actual.as_slice(), weights.as_slice());
+    }
+
+    #[test]
+    fn test_try_from_weights() {
+        assert!(matches!(
+            Ctrnn::try_from_weights(TOPOLOGY, vec![0.0; 35]),
+            Err(NetworkError::TooFewWeights(36, 35))
+        ));
+        assert!(matches!(
+            Ctrnn::try_from_weights(TOPOLOGY, vec![0.0; 37]),
+            Err(NetworkError::LeftoverWeights(36))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_topology() {
+        for topology in [
+            CtrnnTopology {
+                outputs: 5,
+                ..TOPOLOGY
+            },
+            CtrnnTopology {
+                dt: 0.0,
+                ..TOPOLOGY
+            },
+            CtrnnTopology {
+                dt: f32::NAN,
+                ..TOPOLOGY
+            },
+        ] {
+            assert!(matches!(
+                Ctrnn::try_from_weights(topology, vec![0.0; 36]),
+                Err(NetworkError::InvalidTopology(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_integration() {
+        let mut network = integrator(1.0);
+        let input = DVector::from_vec(vec![1.0]);
+
+        let actual: Vec<_> = (0..3)
+            .map(|_| network.propagate(input.clone())[0])
+            .collect();
+
+        assert_relative_eq!(actual.as_slice(), [0.5, 0.75, 0.875].as_slice());
+    }
+
+    #[test]
+    fn test_time_constant_clamped() {
+        let mut network = integrator(0.0);
+        let actual = network.propagate(DVector::from_vec(vec![1.0]));
+
+        assert_relative_eq!(actual.as_slice(), [1.0].as_slice());
+    }
+
+    #[test]
+    fn test_reset_state() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Ctrnn::random(TOPOLOGY, &mut rng);
+        let input = DVector::from_vec(vec![0.3, -0.7, 1.1]);
+
+        let first = network.propagate(input.clone());
+        network.propagate(input.clone());
+        network.reset_state();
+        let after_reset = network.propagate(input);
+
+        assert_relative_eq!(first.as_slice(), after_reset.as_slice());
+    }
+
+    #[test]
+    fn test_propagate_traced() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Ctrnn::random(TOPOLOGY, &mut rng);
+        let mut expected = Ctrnn::from_weights(TOPOLOGY, network.weights());
+        let input = DVector::from_vec(vec![0.3, -0.7, 1.1]);
+
+        let actual = network.propagate_traced(input.clone());
+        let expected = expected.propagate(input);
+
+        assert_eq!(actual.layers[0].post_activation.len(), 4);
+        assert_relative_eq!(actual.output.as_slice(), expected.as_slice());
+        assert_relative_eq!(&actual.layers[0].post_activation[2..], expected.as_slice());
+    }
+
+    #[test]
+    fn test_controller() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Ctrnn::random(TOPOLOGY, &mut rng);
+        let mut controller: Box<dyn Controller> =
+            Box::new(Ctrnn::from_weights(TOPOLOGY, network.weights()));
+        let input = [0.3, -0.7, 1.1];
+        let mut actual = [0.0; 2];
+
+        controller.propagate_into(&input, &mut Scratch::default(), &mut actual);
+
+        let expected = network.propagate(DVector::from_vec(input.to_vec()));
+
+        assert_relative_eq!(actual.as_slice(), expected.as_slice());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::*;
+use std::{error, fmt, io, str};
+
+#[derive(Debug)]
+pub enum NetworkError {
+    /// The network needs at least an input and an output layer.
+    TooFewLayers(usize),
+    /// The weights ran out before every layer was filled.
+    /// (expected, actual)
+    TooFewWeights(usize, usize),
+    /// The layer sizes multiply out to more weights than fit in a `usize`.
+    TooLarge,
+    /// Weights were left over once every layer was filled.
+    /// (expected)
+    LeftoverWeights(usize),
+    /// The connection bits don't cover exactly the input weights.
+    /// (input weights, words of bits)
+    ConnectionsMismatch(usize, usize),
+    /// (found, latest)
+    UnsupportedVersion(u32, u32),
+    /// The topology can't be built, e.g. a `Ctrnn` with more outputs than
+    /// neurons.
+    InvalidTopology(String),
+    /// Parameters the distribution can't sample from, e.g. a negative
+    /// standard deviation.
+    InvalidInitializer(Initializer),
+    /// Parameters the method can't work with, e.g. a tournament of none.
+    InvalidSelection(Selection),
+    /// e.g. a `Linear` multiple below 1 or a temperature that isn't positive.
+    InvalidScaling(Scaling),
+    /// e.g. an `Arithmetic` weight outside of `[0, 1]`.
+    InvalidCrossover(Crossover),
+    /// e.g. a `chance` outside of `[0, 1]` or a `UniformReset` range that's
+    /// empty.
+    InvalidMutation(Mutation),
+    /// e.g. an `Exponential` decay outside of `[0, 1]`, or a `Step` that
+    /// never steps.
+    InvalidSchedule(Schedule),
+    /// A JSON file that isn't valid UTF-8.
+    Utf8(str::Utf8Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewLayers(n) => write!(f, "expected at least 2 layers, got {n}"),
+            Self::TooFewWeights(expected, actual) => {
+                write!(f, "expected {expected} weights, got {actual}")
+            }
+            Self::TooLarge => write!(f, "layers too large to hold their weights"),
+            Self::LeftoverWeights(expected) => {
+                write!(f, "expected {expected} weights, got more")
+            }
+            Self::ConnectionsMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "expected bits for {expected} connections, got {actual} words"
+                )
+            }
+            Self::UnsupportedVersion(found, latest) => {
+                write!(f, "unsupported format version {found} (latest: {latest})")
+            }
+            Self::InvalidTopology(reason) => write!(f, "invalid topology: {reason}"),
+            Self::InvalidInitializer(initializer) => {
+                write!(f, "invalid initializer: {initializer:?}")
+            }
+            Self::InvalidSelection(selection) => write!(f, "invalid selection: {selection:?}"),
+            Self::InvalidScaling(scaling) => write!(f, "invalid scaling: {scaling:?}"),
+            Self::InvalidCrossover(crossover) => write!(f, "invalid crossover: {crossover:?}"),
+            Self::InvalidMutation(mutation) => write!(f, "invalid mutation: {mutation:?}"),
+            Self::InvalidSchedule(schedule) => write!(f, "invalid schedule: {schedule:?}"),
+            Self::Utf8(err) => write!(f, "utf-8: {err}"),
+            Self::Json(err) => write!(f, "json: {err}"),
+            Self::Binary(err) => write!(f, "binary: {err}"),
+            Self::Io(err) => write!(f, "io: {err}"),
+        }
+    }
+}
+
+impl error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Utf8(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Binary(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<str::Utf8Error> for NetworkError {
+    fn from(err: str::Utf8Error) -> Self {
+        Self::Utf8(err)
+    }
+}
+
+impl From<serde_json::Error> for NetworkError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<bincode::Error> for NetworkError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Binary(err)
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -0,0 +1,390 @@
+use crate::*;
+use std::fmt::Write;
+
+const POSITIVE: &str = "#2b6cb0";
+const NEGATIVE: &str = "#c53030";
+
+const NODE_RADIUS: f32 = 14.0;
+const ROW_SPACING: f32 = 40.0;
+const COLUMN_SPACING: f32 = 160.0;
+const MARGIN: f32 = 30.0;
+/// Room for input and output names.
+const LABEL_MARGIN: f32 = 120.0;
+
+/// Labels for the input and output neurons; missing names fall back to
+/// `in 0`, `out 0` and so on.
+#[derive(Clone, Debug, Default)]
+pub struct NeuronNames {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// The network flattened into columns of neurons and weighted edges, shared
+/// by both renderers.
+struct Graph {
+    columns: Vec<Vec<Node>>,
+    edges: Vec<Edge>,
+    max_weight: f32,
+}
+
+struct Node {
+    name: Option<String>,
+    bias: Option<f32>,
+}
+
+struct Edge {
+    /// `(column, neuron)`
+    from: (usize, usize),
+    to: (usize, usize),
+    weight: f32,
+    recurrent: bool,
+}
+
+impl Network {
+    /// Renders a Graphviz graph. Edge width follows the weight's magnitude
+    /// and colour its sign; recurrent edges are dashed, and disconnected
+    /// weights are left out.
+    pub fn to_dot(&self, names: &NeuronNames) -> String {
+        let graph = Graph::new(self, names);
+        let mut dot = String::new();
+
+        dot.push_str("digraph network {\n");
+        dot.push_str("    rankdir=LR;\n    splines=true;\n");
+        dot.push_str("    node [shape=circle, fontsize=10];\n");
+
+        for (col, nodes) in graph.columns.iter().enumerate() {
+            writeln!(dot, "    subgraph column_{col} {{\n        rank=same;").unwrap();
+
+            for (idx, node) in nodes.iter().enumerate() {
+                writeln!(
+                    dot,
+                    "        n{col}_{idx} [label=\"{}\"];",
+                    node.dot_label()
+                )
+                .unwrap();
+            }
+
+            dot.push_str("    }\n");
+        }
+
+        for edge in &graph.edges {
+            writeln!(
+                dot,
+                "    n{}_{} -> n{}_{} [color=\"{}\", penwidth={:.2}{}];",
+                edge.from.0,
+                edge.from.1,
+                edge.to.0,
+                edge.to.1,
+                colour(edge.weight),
+                graph.width(edge.weight),
+                if edge.recurrent {
+                    ", style=dashed, constraint=false"
+                } else {
+                    ""
+                },
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the same picture as `to_dot` directly to SVG, laid out as
+    /// one column per layer.
+    pub fn to_svg(&self, names: &NeuronNames) -> String {
+        let graph = Graph::new(self, names);
+        let rows = graph.columns.iter().map(Vec::len).max().unwrap_or(0);
+
+        let width = 2.0 * (MARGIN + LABEL_MARGIN)
+            + (graph.columns.len().max(1) - 1) as f32 * COLUMN_SPACING;
+        let height = 2.0 * MARGIN + rows.max(1) as f32 * ROW_SPACING;
+
+        let position = |(col, idx): (usize, usize)| {
+            let offset = (rows - graph.columns[col].len()) as f32 * ROW_SPACING / 2.0;
+
+            (
+                MARGIN + LABEL_MARGIN + col as f32 * COLUMN_SPACING,
+                MARGIN + offset + (idx as f32 + 0.5) * ROW_SPACING,
+            )
+        };
+
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"10\">"
+        )
+        .unwrap();
+
+        for edge in &graph.edges {
+            let (x1, y1) = position(edge.from);
+            let (x2, y2) = position(edge.to);
+            let style = format!(
+                "stroke=\"{}\" stroke-width=\"{:.2}\" fill=\"none\"",
+                colour(edge.weight),
+                graph.width(edge.weight)
+            );
+
+            if edge.recurrent {
+                // Bow to the right, so edges within a column stay visible.
+                let bow = COLUMN_SPACING / 3.0;
+
+                writeln!(
+                    svg,
+                    "  <path d=\"M {x1:.1} {y1:.1} C {:.1} {y1:.1} {:.1} {y2:.1} {x2:.1} {y2:.1}\" \
+                     {style} stroke-dasharray=\"4 3\"/>",
+                    x1.max(x2) + bow,
+                    x1.max(x2) + bow,
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    svg,
+                    "  <line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" {style}/>"
+                )
+                .unwrap();
+            }
+        }
+
+        let last = graph.columns.len() - 1;
+
+        for (col, nodes) in graph.columns.iter().enumerate() {
+            for (idx, node) in nodes.iter().enumerate() {
+                let (x, y) = position((col, idx));
+
+                writeln!(
+                    svg,
+                    "  <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{NODE_RADIUS}\" \
+                     fill=\"#ffffff\" stroke=\"#333333\"/>"
+                )
+                .unwrap();
+
+                if let Some(bias) = node.bias {
+                    writeln!(
+                        svg,
+                        "  <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\" \
+                         font-size=\"8\">{bias:.2}</text>",
+                        y + 3.0
+                    )
+                    .unwrap();
+                }
+
+                if let Some(name) = &node.name {
+                    let (x, anchor) = if col == last {
+                        (x + NODE_RADIUS + 4.0, "start")
+                    } else {
+                        (x - NODE_RADIUS - 4.0, "end")
+                    };
+
+                    writeln!(
+                        svg,
+                        "  <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"{anchor}\">{}</text>",
+                        y + 3.0,
+                        escape_xml(name)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Graph {
+    fn new(network: &Network, names: &NeuronNames) -> Self {
+        let last = network.layers.len();
+        let name = |names: &[String], idx: usize, fallback: &str| {
+            names
+                .get(idx)
+                .cloned()
+                .unwrap_or_else(|| format!("{fallback} {idx}"))
+        };
+
+        let inputs = network.layers[0].weights.ncols();
+        let mut columns = vec![(0..inputs)
+            .map(|idx| Node {
+                name: Some(name(&names.inputs, idx, "in")),
+                bias: None,
+            })
+            .collect::<Vec<_>>()];
+
+        let mut edges = Vec::new();
+
+        for (layer_idx, layer) in network.layers.iter().enumerate() {
+            let col = layer_idx + 1;
+
+            columns.push(
+                layer
+                    .biases
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &bias)| Node {
+                        name: (col == last).then(|| name(&names.outputs, idx, "out")),
+                        bias: Some(bias),
+                    })
+                    .collect(),
+            );
+
+            for to in 0..layer.weights.nrows() {
+                for from in 0..layer.weights.ncols() {
+                    if !layer.is_connected(to + from * layer.weights.nrows()) {
+                        continue;
+                    }
+
+                    edges.push(Edge {
+                        from: (col - 1, from),
+                        to: (col, to),
+                        weight: layer.weights[(to, from)],
+                        recurrent: false,
+                    });
+                }
+
+                let source = match layer.kind {
+                    LayerKind::Dense => continue,
+                    LayerKind::Elman => col,
+                    LayerKind::Jordan => last,
+                };
+
+                for from in 0..layer.recurrent.ncols() {
+                    edges.push(Edge {
+                        from: (source, from),
+                        to: (col, to),
+                        weight: layer.recurrent[(to, from)],
+                        recurrent: true,
+                    });
+                }
+            }
+        }
+
+        let max_weight = edges
+            .iter()
+            .map(|edge| edge.weight.abs())
+            .fold(0.0, f32::max);
+
+        Self {
+            columns,
+            edges,
+            max_weight,
+        }
+    }
+
+    fn width(&self, weight: f32) -> f32 {
+        if self.max_weight > 0.0 {
+            0.25 + 3.0 * weight.abs() / self.max_weight
+        } else {
+            0.25
+        }
+    }
+}
+
+impl Node {
+    /// Escaped for a quoted DOT string, with the bias on a second line.
+    fn dot_label(&self) -> String {
+        match (&self.name, self.bias) {
+            (Some(name), Some(bias)) => format!("{}\\nb = {bias:.2}", escape(name)),
+            (Some(name), None) => escape(name),
+            (None, Some(bias)) => format!("b = {bias:.2}"),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+fn colour(weight: f32) -> &'static str {
+    if weight < 0.0 {
+        NEGATIVE
+    } else {
+        POSITIVE
+    }
+}
+
+/// Backslashes go first, so that the ones escaping quotes aren't doubled.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2 inputs, 2 Elman hidden neurons, 1 output.
+    fn network() -> Network {
+        let layers = [
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::ReLU,
+                kind: LayerKind::Elman,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ];
+
+        Network::from_weights(
+            &layers,
+            vec![
+                0.5, -0.5, // biases
+                1.0, -2.0, 0.5, 0.25, // weights
+                0.1, 0.2, 0.3, 0.4, // recurrent
+                0.0, // bias
+                -4.0, 2.0, // weights
+            ],
+        )
+    }
+
+    fn names() -> NeuronNames {
+        NeuronNames {
+            inputs: vec!["left".into(), "back\\slash\\".into()],
+            outputs: vec!["speed \"fwd\"".into()],
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let actual = network().to_dot(&names());
+
+        assert_eq!(actual.matches("->").count(), 4 + 4 + 2);
+        assert_eq!(actual.matches("style=dashed").count(), 4);
+        assert!(actual.contains("n0_0 [label=\"left\"]"));
+        assert!(actual.contains("n0_1 [label=\"back\\\\slash\\\\\"]"));
+        assert!(actual.contains("n1_1 [label=\"b = -0.50\"]"));
+        assert!(actual.contains("n2_0 [label=\"speed \\\"fwd\\\"\\nb = 0.00\"]"));
+        assert!(actual.contains(&format!(
+            "n1_0 -> n2_0 [color=\"{NEGATIVE}\", penwidth=3.25]"
+        )));
+        assert!(actual.contains(&format!(
+            "n1_1 -> n2_0 [color=\"{POSITIVE}\", penwidth=1.75]"
+        )));
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let actual = network().to_svg(&names());
+
+        assert!(actual.starts_with("<svg"));
+        assert!(actual.trim_end().ends_with("</svg>"));
+        assert_eq!(actual.matches("<circle").count(), 5);
+        assert_eq!(actual.matches("<line").count(), 6);
+        assert_eq!(actual.matches("<path").count(), 4);
+        assert!(actual.contains(">left</text>"));
+        assert!(actual.contains(">speed &quot;fwd&quot;</text>"));
+        assert!(actual.contains(">-0.50</text>"));
+    }
+}
@@ -0,0 +1,393 @@
+use crate::*;
+use std::{fs, path::Path, str};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Version 2 added `plasticity`, version 3 `connections`, and version 4
+/// leaves the disconnected weights out. JSON of every version loads, since
+/// the added fields default to absent.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Oldest version whose binary layout matches `NetworkFormat`; bincode
+/// isn't self-describing, so older binaries can't be told apart.
+const MIN_BINARY_VERSION: u32 = 3;
+
+#[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Encoding {
+    Json,
+    Binary,
+}
+
+/// Self-describing form of a `Network`: everything needed to rebuild it
+/// without knowing the topology up front.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkFormat {
+    pub version: u32,
+    pub layers: Vec<LayerTopology>,
+    #[serde(default)]
+    pub plasticity: Option<Plasticity>,
+    /// As in `SparseWeights`; `None` while fully connected.
+    #[serde(default)]
+    pub connections: Option<Vec<u64>>,
+    /// `SparseWeights::values` while `connections` is set, since version 4.
+    pub weights: Vec<f32>,
+}
+
+#[derive(serde::Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl NetworkFormat {
+    pub fn into_network(self) -> Result<Network, NetworkError> {
+        check_version(self.version, 1)?;
+
+        match self.connections {
+            Some(connections) if self.version >= 4 => Network::try_from_sparse_weights(
+                &self.layers,
+                self.plasticity,
+                &SparseWeights {
+                    connections,
+                    values: self.weights,
+                },
+            ),
+            connections => {
+                let mut network =
+                    Network::try_from_plastic_weights(&self.layers, self.plasticity, self.weights)?;
+
+                if let Some(connections) = &connections {
+                    network.try_set_connections(connections)?;
+                }
+
+                Ok(network)
+            }
+        }
+    }
+}
+
+impl From<&Network> for NetworkFormat {
+    fn from(network: &Network) -> Self {
+        let (connections, weights) = match network.connection_bits() {
+            Some(_) => {
+                let sparse = network.sparse_weights();
+
+                (Some(sparse.connections), sparse.values)
+            }
+            None => (None, network.weights().collect()),
+        };
+
+        Self {
+            version: FORMAT_VERSION,
+            layers: network.topology(),
+            plasticity: network.plasticity(),
+            connections,
+            weights,
+        }
+    }
+}
+
+impl Network {
+    pub fn to_json(&self) -> Result<String, NetworkError> {
+        Ok(serde_json::to_string(&NetworkFormat::from(self))?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, NetworkError> {
+        let header: Header = serde_json::from_str(json)?;
+
+        check_version(header.version, 1)?;
+
+        serde_json::from_str::<NetworkFormat>(json)?.into_network()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, NetworkError> {
+        Ok(bincode::serialize(&NetworkFormat::from(self))?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        // The version is the leading field, so it can be checked before the
+        // rest of the layout is trusted.
+        let version: u32 = bincode::deserialize(bytes)?;
+
+        check_version(version, MIN_BINARY_VERSION)?;
+
+        bincode::deserialize::<NetworkFormat>(bytes)?.into_network()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, encoding: Encoding) -> Result<(), NetworkError> {
+        let bytes = match encoding {
+            Encoding::Json => self.to_json()?.into_bytes(),
+            Encoding::Binary => self.to_bytes()?,
+        };
+
+        Ok(fs::write(path, bytes)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>, encoding: Encoding) -> Result<Self, NetworkError> {
+        let bytes = fs::read(path)?;
+
+        match encoding {
+            Encoding::Json => Self::from_json(str::from_utf8(&bytes)?),
+            Encoding::Binary => Self::from_bytes(&bytes),
+        }
+    }
+}
+
+fn check_version(version: u32, min: u32) -> Result<(), NetworkError> {
+    if (min..=FORMAT_VERSION).contains(&version) {
+        Ok(())
+    } else {
+        Err(NetworkError::UnsupportedVersion(version, FORMAT_VERSION))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn network() -> Network {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        Network::random(
+            &[
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 4,
+                    activation: Activation::LeakyReLU(0.1),
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::Tanh,
+                    kind: LayerKind::Dense,
+                },
+            ],
+            &mut rng,
+        )
+    }
+
+    fn assert_same(actual: &Network, expected: &Network) {
+        let actual_weights: Vec<_> = actual.weights().collect();
+        let expected_weights: Vec<_> = expected.weights().collect();
+
+        assert_eq!(actual.topology(), expected.topology());
+        assert_relative_eq!(actual_weights.as_slice(), expected_weights.as_slice());
+    }
+
+    #[test]
+    fn test_json() {
+        let network = network();
+        let actual = Network::from_json(&network.to_json().unwrap()).unwrap();
+
+        assert_same(&actual, &network);
+    }
+
+    #[test]
+    fn test_binary() {
+        let network = network();
+        let actual = Network::from_bytes(&network.to_bytes().unwrap()).unwrap();
+
+        assert_same(&actual, &network);
+    }
+
+    #[test]
+    fn test_plastic_sparse() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network().with_plasticity(Plasticity::Abcd, &mut rng);
+
+        network.prune(0.5);
+        network.propagate(DVector::from_vec(vec![0.5, -0.5, 1.0]));
+
+        for actual in [
+            Network::from_json(&network.to_json().unwrap()).unwrap(),
+            Network::from_bytes(&network.to_bytes().unwrap()).unwrap(),
+        ] {
+            assert_eq!(actual.plasticity(), Some(Plasticity::Abcd));
+            assert_relative_eq!(actual.sparsity(), network.sparsity());
+            assert_same(&actual, &network);
+        }
+
+        // Only the connected weights are saved.
+        let format = NetworkFormat::from(&network);
+        let disconnected = 3 * 4 + 4 * 2 - network.connections();
+
+        assert_eq!(
+            format.weights.len(),
+            network.weights().count() - disconnected
+        );
+    }
+
+    /// Version 3 saved the disconnected weights too, as zeros.
+    #[test]
+    fn test_json_v3_sparse() {
+        let mut network = network();
+
+        network.prune(0.5);
+
+        let format = NetworkFormat {
+            version: 3,
+            connections: network.connection_bits(),
+            weights: network.weights().collect(),
+            ..NetworkFormat::from(&network)
+        };
+        let actual = Network::from_json(&serde_json::to_string(&format).unwrap()).unwrap();
+
+        assert_relative_eq!(actual.sparsity(), network.sparsity());
+        assert_same(&actual, &network);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let network = network();
+        let path = std::env::temp_dir().join("lib-neural-network-test-save-load.bin");
+
+        network.save(&path, Encoding::Binary).unwrap();
+        let actual = Network::load(&path, Encoding::Binary).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_same(&actual, &network);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut format = NetworkFormat::from(&network());
+        format.version = FORMAT_VERSION + 1;
+
+        let json = serde_json::to_string(&format).unwrap();
+        let bytes = bincode::serialize(&format).unwrap();
+
+        assert!(matches!(
+            Network::from_json(&json),
+            Err(NetworkError::UnsupportedVersion(5, 4))
+        ));
+        assert!(matches!(
+            Network::from_bytes(&bytes),
+            Err(NetworkError::UnsupportedVersion(5, 4))
+        ));
+    }
+
+    /// As saved before `plasticity` and `connections` existed.
+    #[test]
+    fn test_json_v1() {
+        let json = r#"{
+            "version": 1,
+            "layers": [
+                {"neurons": 2, "activation": "Identity"},
+                {"neurons": 1, "activation": "Tanh"}
+            ],
+            "weights": [0.5, 0.1, -0.2]
+        }"#;
+        let mut network = Network::from_json(json).unwrap();
+
+        assert_eq!(network.plasticity(), None);
+        assert_eq!(network.topology()[1].kind, LayerKind::Dense);
+        assert_relative_eq!(
+            network
+                .propagate(DVector::from_vec(vec![1.0, 2.0]))
+                .as_slice(),
+            [(0.5f32 + 0.1 - 0.4).tanh()].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_binary_v2() {
+        let mut format = NetworkFormat::from(&network());
+        format.version = 2;
+
+        assert!(matches!(
+            Network::from_bytes(&bincode::serialize(&format).unwrap()),
+            Err(NetworkError::UnsupportedVersion(2, 4))
+        ));
+    }
+
+    #[test]
+    fn test_too_few_weights() {
+        let mut format = NetworkFormat::from(&network());
+        format.weights.pop();
+
+        assert!(matches!(
+            format.into_network(),
+            Err(NetworkError::TooFewWeights(26, 25))
+        ));
+    }
+
+    #[test]
+    fn test_leftover_weights() {
+        let mut format = NetworkFormat::from(&network());
+        format.weights.push(0.0);
+
+        assert!(matches!(
+            format.into_network(),
+            Err(NetworkError::LeftoverWeights(26))
+        ));
+    }
+
+    #[test]
+    fn test_too_few_layers() {
+        let mut format = NetworkFormat::from(&network());
+        format.layers.truncate(1);
+
+        assert!(matches!(
+            format.into_network(),
+            Err(NetworkError::TooFewLayers(1))
+        ));
+    }
+
+    #[test]
+    fn test_load_invalid_utf8() {
+        let mut json = network().to_json().unwrap().into_bytes();
+        let path = std::env::temp_dir().join("lib-neural-network-test-invalid-utf8.json");
+
+        // In an ignored field, so that lossy decoding would still parse.
+        json.pop();
+        json.extend(b",\"note\":\"\xff\"}");
+        fs::write(&path, json).unwrap();
+        let actual = Network::load(&path, Encoding::Json);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(actual, Err(NetworkError::Utf8(_))));
+    }
+
+    /// Sizes way beyond the weights must fail cleanly rather than overflow
+    /// or try to allocate them.
+    #[test]
+    fn test_huge_layers() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for _ in 0..100 {
+            let mut format = NetworkFormat::from(&network());
+
+            for layer in &mut format.layers {
+                layer.neurons = rng.gen::<usize>() >> rng.gen_range(0..44);
+                layer.kind = if rng.gen() {
+                    LayerKind::Dense
+                } else {
+                    LayerKind::Jordan
+                };
+            }
+
+            let json = serde_json::to_string(&format).unwrap();
+            let bytes = bincode::serialize(&format).unwrap();
+
+            assert!(Network::from_json(&json).is_err(), "{:?}", format.layers);
+            assert!(Network::from_bytes(&bytes).is_err(), "{:?}", format.layers);
+        }
+    }
+
+    #[test]
+    fn test_garbage() {
+        assert!(matches!(
+            Network::from_json("{\"version\": 3}"),
+            Err(NetworkError::Json(_))
+        ));
+        assert!(matches!(
+            Network::from_bytes(&[3, 0, 0, 0]),
+            Err(NetworkError::Binary(_))
+        ));
+    }
+}
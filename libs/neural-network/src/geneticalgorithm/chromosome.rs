@@ -1,7 +1,16 @@
 use std::ops::Index;
 
+#[derive(Clone, Debug)]
 pub struct Chromosome {
     genes: Vec<f32>,
+    /// Step sizes of `Mutation::SelfAdaptive`, evolving alongside the genes:
+    /// none, one for the whole chromosome, or one per gene. They aren't
+    /// part of what `iter` and `into_iter` yield.
+    strategy: Vec<f32>,
+    /// `SparseWeights::connections` of the network the genes came from,
+    /// empty while it's fully connected. Crossover takes them from the
+    /// first parent.
+    connections: Vec<u64>,
 }
 
 #[allow(clippy::len_without_is_empty)]
@@ -17,6 +26,28 @@ impl Chromosome {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut f32> {
         self.genes.iter_mut()
     }
+
+    pub fn strategy(&self) -> &[f32] {
+        &self.strategy
+    }
+
+    pub fn with_strategy(mut self, strategy: Vec<f32>) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn connections(&self) -> &[u64] {
+        &self.connections
+    }
+
+    pub fn with_connections(mut self, connections: Vec<u64>) -> Self {
+        self.connections = connections;
+        self
+    }
+
+    pub(crate) fn strategy_mut(&mut self) -> &mut Vec<f32> {
+        &mut self.strategy
+    }
 }
 
 impl Index<usize> for Chromosome {
@@ -31,6 +62,8 @@ impl FromIterator<f32> for Chromosome {
     fn from_iter<T: IntoIterator<Item = f32>>(iter: T) -> Self {
         Self {
             genes: iter.into_iter().collect(),
+            strategy: Vec::new(),
+            connections: Vec::new(),
         }
     }
 }
@@ -48,5 +81,7 @@ impl IntoIterator for Chromosome {
 impl PartialEq for Chromosome {
     fn eq(&self, other: &Self) -> bool {
         approx::relative_eq!(self.genes.as_slice(), other.genes.as_slice())
+            && approx::relative_eq!(self.strategy.as_slice(), other.strategy.as_slice())
+            && self.connections == other.connections
     }
 }
@@ -4,17 +4,141 @@ use strum_macros::{EnumIter, IntoStaticStr};
 #[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
 pub enum Crossover {
     Uniform,
+    /// Genes of `parent_a` up to a random point, then `parent_b`'s.
+    SinglePoint,
+    /// Alternates between the parents at `k` distinct random points.
+    /// (k)
+    KPoint(usize),
+    /// `alpha * a + (1 - alpha) * b`, gene by gene.
+    /// (alpha)
+    Arithmetic(f32),
+    /// BLX-alpha: uniform within the parents' interval, widened by `alpha`
+    /// times its length on both sides.
+    /// (alpha)
+    Blend(f32),
+    /// SBX: spreads around the parents like single-point crossover of
+    /// binary genes would; the higher `eta`, the closer to them.
+    /// (eta)
+    SimulatedBinary(f32),
+    /// Takes every neuron whole from either parent; needs a `NetworkLayout`.
+    Neuron,
+    /// Takes every layer whole from either parent; needs a `NetworkLayout`.
+    Layer,
 }
 
 impl Crossover {
+    /// Every method with sensible parameters, e.g. for frontends that only
+    /// let users pick by name.
+    pub fn presets() -> [Self; 8] {
+        [
+            Self::Uniform,
+            Self::SinglePoint,
+            Self::KPoint(2),
+            Self::Arithmetic(0.5),
+            Self::Blend(0.5),
+            Self::SimulatedBinary(2.0),
+            Self::Neuron,
+            Self::Layer,
+        ]
+    }
+
+    /// `KPoint` needs a point, `Arithmetic` a weight in `[0, 1]`, and
+    /// `Blend` and `SimulatedBinary` finite, non-negative parameters.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let valid = match *self {
+            Self::KPoint(k) => k > 0,
+            Self::Arithmetic(alpha) => (0.0..=1.0).contains(&alpha),
+            Self::Blend(alpha) => alpha.is_finite() && alpha >= 0.0,
+            Self::SimulatedBinary(eta) => eta.is_finite() && eta >= 0.0,
+            Self::Uniform | Self::SinglePoint | Self::Neuron | Self::Layer => true,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidCrossover(*self))
+        }
+    }
+
     pub fn crossover(
         &self,
         rng: &mut dyn RngCore,
         parent_a: &Chromosome,
         parent_b: &Chromosome,
     ) -> Chromosome {
-        match self {
+        self.crossover_with(rng, parent_a, parent_b, None)
+    }
+
+    /// `Neuron` and `Layer` fall back to `Uniform` without a `layout`,
+    /// e.g. for controllers other than `Network`; the rest ignore it.
+    ///
+    /// Strategy parameters are averaged, as is usual in evolution
+    /// strategies, whatever the method; if the parents' don't match, the
+    /// child gets `parent_a`'s.
+    pub fn crossover_with(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        layout: Option<&NetworkLayout>,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        let (strategy_a, strategy_b) = (parent_a.strategy(), parent_b.strategy());
+        let strategy = if strategy_a.len() == strategy_b.len() {
+            strategy_a
+                .iter()
+                .zip(strategy_b)
+                .map(|(a, b)| 0.5 * (a + b))
+                .collect()
+        } else {
+            strategy_a.to_vec()
+        };
+
+        self.crossover_genes(rng, parent_a, parent_b, layout)
+            .with_strategy(strategy)
+            .with_connections(parent_a.connections().to_vec())
+    }
+
+    fn crossover_genes(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        layout: Option<&NetworkLayout>,
+    ) -> Chromosome {
+        match *self {
+            Self::Neuron | Self::Layer => match layout {
+                Some(layout) => self.structured(rng, parent_a, parent_b, layout),
+                None => self.uniform(rng, parent_a, parent_b),
+            },
             Self::Uniform => self.uniform(rng, parent_a, parent_b),
+            Self::SinglePoint => self.k_point(rng, parent_a, parent_b, 1),
+            Self::KPoint(k) => self.k_point(rng, parent_a, parent_b, k),
+            Self::Arithmetic(alpha) => {
+                genewise(parent_a, parent_b, |a, b| alpha * a + (1.0 - alpha) * b)
+            }
+            Self::Blend(alpha) => genewise(parent_a, parent_b, |a, b| {
+                let (min, max) = (a.min(b), a.max(b));
+                let margin = alpha * (max - min);
+
+                if margin > 0.0 {
+                    rng.gen_range(min - margin..max + margin)
+                } else {
+                    a
+                }
+            }),
+            Self::SimulatedBinary(eta) => genewise(parent_a, parent_b, |a, b| {
+                let u: f32 = rng.gen();
+                let beta = if u <= 0.5 {
+                    (2.0 * u).powf(1.0 / (eta + 1.0))
+                } else {
+                    (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (eta + 1.0))
+                };
+                let (a, b) = if rng.gen_bool(0.5) { (a, b) } else { (b, a) };
+
+                0.5 * ((1.0 + beta) * a + (1.0 - beta) * b)
+            }),
         }
     }
 
@@ -24,8 +148,6 @@ impl Crossover {
         parent_a: &Chromosome,
         parent_b: &Chromosome,
     ) -> Chromosome {
-        assert_eq!(parent_a.len(), parent_b.len());
-
         let parent_a = parent_a.iter();
         let parent_b = parent_b.iter();
 
@@ -34,6 +156,96 @@ impl Crossover {
             .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
             .collect()
     }
+
+    /// Crossover points fall between genes, so there can be at most
+    /// `len - 1` of them.
+    fn k_point(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        k: usize,
+    ) -> Chromosome {
+        let len = parent_a.len();
+
+        if len < 2 {
+            return parent_a.iter().copied().collect();
+        }
+
+        let mut points = rand::seq::index::sample(rng, len - 1, k.min(len - 1)).into_vec();
+
+        points.sort_unstable();
+
+        let mut points = points.into_iter().map(|point| point + 1).peekable();
+        let mut from_a = true;
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(idx, (&a, &b))| {
+                if points.next_if_eq(&idx).is_some() {
+                    from_a = !from_a;
+                }
+
+                if from_a {
+                    a
+                } else {
+                    b
+                }
+            })
+            .collect()
+    }
+}
+
+impl Crossover {
+    /// Genes outside of `layout` are picked one by one, as by `Uniform`.
+    fn structured(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+        layout: &NetworkLayout,
+    ) -> Chromosome {
+        assert!(parent_a.len() >= layout.len());
+
+        let units = match self {
+            Self::Layer => layout.layers(),
+            _ => layout.neurons(),
+        };
+        let from_a: Vec<_> = (0..units).map(|_| rng.gen_bool(0.5)).collect();
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(idx, (&a, &b))| {
+                let from_a = match (self, layout.unit(idx)) {
+                    (Self::Layer, Some((layer, _))) => from_a[layer],
+                    (_, Some((_, neuron))) => from_a[neuron],
+                    (_, None) => rng.gen_bool(0.5),
+                };
+
+                if from_a {
+                    a
+                } else {
+                    b
+                }
+            })
+            .collect()
+    }
+}
+
+fn genewise(
+    parent_a: &Chromosome,
+    parent_b: &Chromosome,
+    mut f: impl FnMut(f32, f32) -> f32,
+) -> Chromosome {
+    parent_a
+        .iter()
+        .zip(parent_b.iter())
+        .map(|(&a, &b)| f(a, b))
+        .collect()
 }
 
 #[cfg(test)]
@@ -54,4 +266,244 @@ mod test {
         assert_eq!(diff_a, 49);
         assert_eq!(diff_b, 51);
     }
+
+    fn parents() -> (Chromosome, Chromosome) {
+        (
+            (1..=100).map(|x| x as f32).collect(),
+            (1..=100).map(|x| -x as f32).collect(),
+        )
+    }
+
+    /// Where the child switches from taking one parent's gene to the
+    /// other's.
+    fn switches(child: &Chromosome, parent_a: &Chromosome) -> usize {
+        let from_a: Vec<_> = child
+            .iter()
+            .zip(parent_a.iter())
+            .map(|(c, a)| c == a)
+            .collect();
+
+        from_a.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn test_single_point() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        for _ in 0..10 {
+            let child = Crossover::SinglePoint.crossover(&mut rng, &parent_a, &parent_b);
+
+            assert_eq!(child[0], parent_a[0]);
+            assert_eq!(child[99], parent_b[99]);
+            assert_eq!(switches(&child, &parent_a), 1);
+        }
+    }
+
+    #[test]
+    fn test_k_point() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+
+        for k in [2, 5, 99, 200] {
+            let child = Crossover::KPoint(k).crossover(&mut rng, &parent_a, &parent_b);
+
+            assert_eq!(switches(&child, &parent_a), k.min(99));
+            assert!(child
+                .iter()
+                .enumerate()
+                .all(|(idx, &c)| c == parent_a[idx] || c == parent_b[idx]));
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+        let child = Crossover::Arithmetic(0.75).crossover(&mut rng, &parent_a, &parent_b);
+
+        assert!(child
+            .iter()
+            .enumerate()
+            .all(|(idx, &c)| c == 0.5 * (idx + 1) as f32));
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+        let child = Crossover::Blend(0.5).crossover(&mut rng, &parent_a, &parent_b);
+
+        // Within `-2 x..2 x`, and reaching past the parents somewhere.
+        assert!(child
+            .iter()
+            .enumerate()
+            .all(|(idx, c)| c.abs() <= 2.0 * (idx + 1) as f32));
+        assert!(child
+            .iter()
+            .enumerate()
+            .any(|(idx, c)| c.abs() > (idx + 1) as f32));
+    }
+
+    #[test]
+    fn test_simulated_binary() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+        let spread = |eta: f32, rng: &mut ChaCha8Rng| {
+            let child = Crossover::SimulatedBinary(eta).crossover(rng, &parent_a, &parent_b);
+
+            // Distance from the nearest parent, relative to their distance.
+            child
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| {
+                    let x = (idx + 1) as f32;
+
+                    (c.abs() - x).abs() / (2.0 * x)
+                })
+                .sum::<f32>()
+                / 100.0
+        };
+
+        assert!(spread(1000.0, &mut rng) < 0.01);
+        assert!(spread(1.0, &mut rng) > 0.1);
+
+        // Identical parents make identical children.
+        let child = Crossover::SimulatedBinary(2.0).crossover(&mut rng, &parent_a, &parent_a);
+
+        assert!(child == parent_a);
+    }
+
+    fn layers() -> [LayerTopology; 3] {
+        [
+            LayerTopology {
+                neurons: 3,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 4,
+                activation: Activation::Tanh,
+                kind: LayerKind::Jordan,
+            },
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ]
+    }
+
+    /// Child of parents with all positive and all negative genes, with
+    /// two extra genes past the network's.
+    fn structured(method: Crossover) -> (NetworkLayout, Chromosome) {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layout = NetworkLayout::new(&layers(), Some(Plasticity::Abcd));
+        let parent_a: Chromosome = (0..layout.len() + 2).map(|x| x as f32 + 1.0).collect();
+        let parent_b: Chromosome = parent_a.iter().map(|x| -x).collect();
+
+        let child = method.crossover_with(&mut rng, &parent_a, &parent_b, Some(&layout));
+
+        (layout, child)
+    }
+
+    #[test]
+    fn test_neuron() {
+        let (layout, child) = structured(Crossover::Neuron);
+        let mut from_a = vec![Vec::new(); 6];
+
+        for idx in 0..layout.len() {
+            from_a[layout.unit(idx).unwrap().1].push(child[idx] > 0.0);
+        }
+
+        // Every neuron comes whole from one parent, and both contribute.
+        assert!(from_a
+            .iter()
+            .all(|genes| genes.iter().all(|&g| g == genes[0])));
+        assert!(from_a.iter().any(|genes| genes[0]));
+        assert!(from_a.iter().any(|genes| !genes[0]));
+        assert_eq!(child.len(), layout.len() + 2);
+    }
+
+    #[test]
+    fn test_layer() {
+        let (layout, child) = structured(Crossover::Layer);
+
+        for layer in 0..2 {
+            let signs: Vec<_> = (0..layout.len())
+                .filter(|&idx| layout.unit(idx).unwrap().0 == layer)
+                .map(|idx| child[idx] > 0.0)
+                .collect();
+
+            assert!(signs.iter().all(|&sign| sign == signs[0]));
+        }
+    }
+
+    #[test]
+    fn test_without_layout() {
+        let (parent_a, parent_b) = parents();
+
+        for method in [Crossover::Neuron, Crossover::Layer] {
+            let child = method.crossover(
+                &mut ChaCha8Rng::from_seed(Default::default()),
+                &parent_a,
+                &parent_b,
+            );
+            let uniform = Crossover::Uniform.crossover(
+                &mut ChaCha8Rng::from_seed(Default::default()),
+                &parent_a,
+                &parent_b,
+            );
+
+            assert!(child == uniform, "{method:?}");
+        }
+    }
+
+    #[test]
+    fn test_strategy() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+        let parent_a = parent_a.with_strategy(vec![0.1, 0.2]);
+
+        let child = Crossover::SinglePoint.crossover(&mut rng, &parent_a, &parent_b);
+
+        assert_eq!(child.strategy(), [0.1, 0.2]);
+
+        let parent_b = parent_b.with_strategy(vec![0.3, 0.4]);
+        let child = Crossover::Uniform.crossover(&mut rng, &parent_a, &parent_b);
+
+        approx::assert_relative_eq!(child.strategy(), [0.2, 0.3].as_slice());
+    }
+
+    #[test]
+    fn test_connections() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (parent_a, parent_b) = parents();
+        let parent_a = parent_a.with_connections(vec![0b1011]);
+        let parent_b = parent_b.with_connections(vec![0b0110]);
+
+        let child = Crossover::Uniform.crossover(&mut rng, &parent_a, &parent_b);
+
+        assert_eq!(child.connections(), [0b1011]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for method in [
+            Crossover::KPoint(0),
+            Crossover::Arithmetic(1.5),
+            Crossover::Blend(-0.5),
+            Crossover::Blend(f32::INFINITY),
+            Crossover::SimulatedBinary(f32::NAN),
+        ] {
+            assert!(
+                matches!(method.check(), Err(NetworkError::InvalidCrossover(_))),
+                "{method:?}"
+            );
+        }
+
+        for method in Crossover::presets() {
+            assert!(method.check().is_ok(), "{method:?}");
+        }
+    }
 }
@@ -4,6 +4,19 @@ pub trait Individual {
     fn fitness(&self) -> f32;
     fn chromosome(&self) -> &Chromosome;
     fn create(chromosome: Chromosome) -> Self;
+
+    /// Copies an elite unchanged into the next generation, see
+    /// `GeneticAlgorithm::with_elitism`; it keeps `fitness` instead of
+    /// earning a new one, unless that's `None`. By default it's evaluated
+    /// again, like any other child.
+    fn create_elite(chromosome: Chromosome, fitness: Option<f32>) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = fitness;
+
+        Self::create(chromosome)
+    }
 }
 
 #[cfg(test)]
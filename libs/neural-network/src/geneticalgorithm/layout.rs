@@ -0,0 +1,124 @@
+use crate::*;
+
+/// Which layer and neuron every gene of a `Network::weights` chromosome
+/// belongs to, for crossovers that keep them together.
+///
+/// A neuron is its bias, its incoming and recurrent weights, and their
+/// plasticity coefficients. Genes past the network's own, such as strategy
+/// parameters, belong to neither.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkLayout {
+    /// `(layer, neuron)` per gene, with neurons numbered across layers.
+    genes: Vec<(usize, usize)>,
+    layers: usize,
+    neurons: usize,
+}
+
+impl NetworkLayout {
+    pub fn new(layers: &[LayerTopology], plasticity: Option<Plasticity>) -> Self {
+        assert!(layers.len() >= 2);
+
+        let output_size = layers[layers.len() - 1].neurons;
+        let mut genes = Vec::new();
+        let mut coefficients = Vec::new();
+        let mut offset = 0;
+
+        for (layer, [fst, snd]) in layers.array_windows::<2>().enumerate() {
+            let n = snd.neurons;
+            let neuron = |idx: usize| (layer, offset + idx % n);
+
+            genes.extend((0..n).map(neuron));
+            genes.extend((0..fst.neurons * n).map(neuron));
+            genes.extend((0..snd.context_size(output_size) * n).map(neuron));
+
+            if let Some(rule) = plasticity {
+                coefficients.extend(
+                    (0..fst.neurons * n * rule.genes()).map(|idx| neuron(idx / rule.genes())),
+                );
+            }
+
+            offset += n;
+        }
+
+        genes.extend(coefficients);
+
+        Self {
+            genes,
+            layers: layers.len() - 1,
+            neurons: offset,
+        }
+    }
+
+    /// Number of genes the network accounts for.
+    pub fn len(&self) -> usize {
+        self.genes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    /// `(layer, neuron)` of gene `idx`, if it belongs to one.
+    pub fn unit(&self, idx: usize) -> Option<(usize, usize)> {
+        self.genes.get(idx).copied()
+    }
+
+    pub(crate) fn layers(&self) -> usize {
+        self.layers
+    }
+
+    pub(crate) fn neurons(&self) -> usize {
+        self.neurons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let layers = [
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Tanh,
+                kind: LayerKind::Elman,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ];
+        let layout = NetworkLayout::new(&layers, Some(Plasticity::Hebbian));
+        let network: Network = Network::from_plastic_weights(
+            &layers,
+            Some(Plasticity::Hebbian),
+            (0..layout.len()).map(|x| x as f32),
+        );
+
+        assert_eq!(layout.len(), network.weights().count());
+        assert_eq!((layout.layers(), layout.neurons()), (2, 3));
+
+        let (layers, neurons): (Vec<_>, Vec<_>) = (0..layout.len())
+            .map(|idx| layout.unit(idx).unwrap())
+            .unzip();
+
+        // Biases, weights and recurrent weights of the Elman layer, then the
+        // output layer's bias and weights, then the coefficients of both.
+        assert_eq!(
+            neurons,
+            [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 0, 1, 0, 1, 2, 2]
+        );
+        assert_eq!(
+            layers,
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 1, 1]
+        );
+        assert_eq!(layout.unit(layout.len()), None);
+    }
+}
@@ -1,11 +1,15 @@
 pub use self::{
-    chromosome::*, crossover::*, individual::*, mutation::*, selection::*, statistics::*,
+    chromosome::*, crossover::*, individual::*, layout::*, mutation::*, scaling::*, schedule::*,
+    selection::*, statistics::*,
 };
 
 mod chromosome;
 pub mod crossover;
 mod individual;
+mod layout;
 pub mod mutation;
+pub mod scaling;
+pub mod schedule;
 pub mod selection;
 mod statistics;
 
@@ -15,6 +19,17 @@ pub struct GeneticAlgorithm {
     selection_method: Selection,
     crossover_method: Crossover,
     mutation_method: Mutation,
+    scaling: Scaling,
+    layout: Option<NetworkLayout>,
+    schedules: Schedules,
+    elites: usize,
+    reevaluate_elites: bool,
+    /// Generations evolved so far, for anything that changes over time.
+    generation: usize,
+    /// Best `Statistics::max_fitness` so far, and how many generations ago
+    /// it last improved, for `Schedule::Adaptive`.
+    best_fitness: f32,
+    stagnation: usize,
 }
 
 impl GeneticAlgorithm {
@@ -27,29 +42,190 @@ impl GeneticAlgorithm {
             selection_method,
             crossover_method,
             mutation_method,
+            scaling: Scaling::default(),
+            layout: None,
+            schedules: Schedules::default(),
+            elites: 0,
+            reevaluate_elites: true,
+            generation: 0,
+            best_fitness: f32::NEG_INFINITY,
+            stagnation: 0,
         }
     }
 
-    pub fn evolve<I>(&self, rng: &mut dyn RngCore, population: &[I]) -> (Vec<I>, Statistics)
+    /// What `selection_method` gets instead of the raw fitness.
+    pub fn with_scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// How the chromosomes map onto a `Network`, for `Crossover::Neuron`
+    /// and `Crossover::Layer`.
+    pub fn with_layout(mut self, layout: NetworkLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Lets the methods' parameters change over the generations.
+    pub fn with_schedules(mut self, schedules: Schedules) -> Self {
+        self.schedules = schedules;
+        self
+    }
+
+    /// Copies the `count` fittest unchanged into the next generation. With
+    /// `reevaluate`, they earn their fitness anew, as everyone else does;
+    /// without, they keep the one they were picked for.
+    pub fn with_elitism(mut self, count: usize, reevaluate: bool) -> Self {
+        self.elites = count;
+        self.reevaluate_elites = reevaluate;
+        self
+    }
+
+    /// Whether every method and schedule has parameters it can work with,
+    /// which `evolve` takes for granted.
+    pub fn check(&self) -> Result<(), NetworkError> {
+        self.selection_method.check()?;
+        self.crossover_method.check()?;
+        self.mutation_method.check()?;
+        self.scaling.check()?;
+        self.schedules.check()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Generations since `Statistics::max_fitness` last improved.
+    pub fn stagnation(&self) -> usize {
+        self.stagnation
+    }
+
+    /// The methods as they are this generation, with `schedules` applied.
+    pub fn scheduled(&self) -> (Selection, Scaling, Mutation) {
+        // Counts NaN, e.g. a `Step` that overflowed into `0 * inf`, as the
+        // lowest value.
+        let value = |schedule: &Schedule| {
+            let value = schedule.value(self.generation, self.stagnation);
+
+            if value.is_nan() {
+                f32::NEG_INFINITY
+            } else {
+                value
+            }
+        };
+        let Schedules {
+            mutation_chance,
+            mutation_coeff,
+            tournament_size,
+            temperature,
+        } = &self.schedules;
+
+        let mut selection = self.selection_method;
+        let mut scaling = self.scaling;
+        let mut mutation = self.mutation_method;
+
+        if let Some(schedule) = tournament_size {
+            selection = selection.with_tournament_size(value(schedule).round().max(1.0) as usize);
+        }
+        // Kept within what the methods accept, so that a schedule that
+        // overshoots doesn't panic halfway through a run.
+        if let Some(schedule) = temperature {
+            scaling = scaling.with_temperature(value(schedule).clamp(MIN_SCHEDULED, f32::MAX));
+        }
+        if let Some(schedule) = mutation_chance {
+            mutation = mutation.with_chance(value(schedule).clamp(0.0, 1.0));
+        }
+        if let Some(schedule) = mutation_coeff {
+            mutation = mutation.with_coeff(value(schedule).clamp(MIN_SCHEDULED, f32::MAX));
+        }
+
+        (selection, scaling, mutation)
+    }
+
+    /// Returns the next generation, and `Statistics` of `population`.
+    pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, population: &[I]) -> (Vec<I>, Statistics)
     where
         I: Individual,
     {
         assert!(!population.is_empty());
 
-        let new_pop = (0..population.len())
-            .map(|_| {
-                let parent_a = self.selection_method.select(rng, population).chromosome();
-                let parent_b = self.selection_method.select(rng, population).chromosome();
-                let mut child = self.crossover_method.crossover(rng, parent_a, parent_b);
+        let statistics = Statistics::new(population);
 
-                self.mutation_method.mutate(rng, &mut child);
+        if statistics.max_fitness() > self.best_fitness {
+            self.best_fitness = statistics.max_fitness();
+            self.stagnation = 0;
+        } else {
+            self.stagnation += 1;
+        }
 
-                I::create(child)
+        let (selection, scaling, mutation) = self.scheduled();
+        let fitness: Vec<_> = population
+            .iter()
+            .map(|individual| individual.fitness())
+            .collect();
+        let scaled = scaling.apply(&fitness, self.generation);
+
+        let mut fittest: Vec<_> = (0..population.len()).collect();
+        fittest.sort_by(|&a, &b| fitness[b].total_cmp(&fitness[a]));
+        fittest.truncate(self.elites);
+
+        let mut new_pop: Vec<_> = fittest
+            .iter()
+            .map(|&idx| {
+                I::create_elite(
+                    population[idx].chromosome().clone(),
+                    (!self.reevaluate_elites).then_some(fitness[idx]),
+                )
             })
             .collect();
 
-        let statistics = Statistics::new(population);
+        let parents = selection.pick(rng, &scaled, 2 * (population.len() - fittest.len()));
+
+        for parents in parents.chunks_exact(2) {
+            let parent_a = population[parents[0]].chromosome();
+            let parent_b = population[parents[1]].chromosome();
+            let mut child =
+                self.crossover_method
+                    .crossover_with(rng, parent_a, parent_b, self.layout.as_ref());
+
+            mutation.mutate(rng, &mut child);
+            new_pop.push(I::create(child));
+        }
+
+        self.generation += 1;
 
         (new_pop, statistics)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_elitism() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut ga = GeneticAlgorithm::new(
+            Selection::Roulette,
+            Crossover::Uniform,
+            Mutation::UniformPerturbation(1.0, 1.0),
+        )
+        .with_elitism(2, true);
+
+        let population: Vec<_> = [[1.0, 2.0], [4.0, 5.0], [0.0, 1.0], [3.0, 3.0]]
+            .into_iter()
+            .map(|genes| TestIndividual::create(genes.into_iter().collect()))
+            .collect();
+
+        let (new_pop, _) = ga.evolve(&mut rng, &population);
+
+        assert_eq!(new_pop.len(), population.len());
+        assert!(new_pop[0] == population[1]);
+        assert!(new_pop[1] == population[3]);
+        assert!(new_pop[2..]
+            .iter()
+            .all(|child| population.iter().all(|individual| child != individual)));
+    }
+}
@@ -1,23 +1,195 @@
 use crate::*;
+use rand_distr::{Cauchy, Distribution, Normal, StandardNormal};
 use strum_macros::{EnumIter, IntoStaticStr};
 
+/// Every variant but `SelfAdaptive` mutates each gene independently with
+/// probability `chance`.
 #[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Mutation {
-    /// Mutation with a gaussian distribution.
+    /// Adds a uniform `-coeff..coeff`.
     /// (chance, coeff)
+    UniformPerturbation(f32, f32),
+    /// Adds a normal `N(0, sigma)`.
+    /// (chance, sigma)
     Gaussian(f32, f32),
+    /// Adds a Cauchy-distributed value, whose heavy tails occasionally make
+    /// large jumps.
+    /// (chance, scale)
+    Cauchy(f32, f32),
+    /// Replaces the gene with a uniform `min..max`.
+    /// (chance, min, max)
+    UniformReset(f32, f32, f32),
+    /// Deb's bounded polynomial mutation; the higher `eta`, the smaller the
+    /// steps. Genes end up within `min..=max`.
+    /// (chance, eta, min, max)
+    Polynomial(f32, f32, f32, f32),
+    /// Evolution-strategy mutation: the chromosome's own step sizes are
+    /// first scaled log-normally, then every gene gets `N(0, sigma)`. Step
+    /// sizes start out at `initial_sigma`.
+    /// (strategy, initial_sigma)
+    SelfAdaptive(Strategy, f32),
 }
 
+/// How many step sizes `Mutation::SelfAdaptive` evolves.
+#[derive(
+    IntoStaticStr,
+    EnumIter,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Strategy {
+    #[default]
+    PerGenome,
+    PerGene,
+}
+
+/// Step sizes never shrink below this, so that mutation can't stall.
+const MIN_SIGMA: f32 = 1e-5;
+
 impl Mutation {
-    pub fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+    /// Every method with sensible parameters, e.g. for frontends that only
+    /// let users pick by name.
+    pub fn presets() -> [Self; 6] {
+        [
+            Self::UniformPerturbation(0.01, 0.3),
+            Self::Gaussian(0.01, 0.2),
+            Self::Cauchy(0.01, 0.1),
+            Self::UniformReset(0.01, -1.0, 1.0),
+            Self::Polynomial(0.01, 20.0, -5.0, 5.0),
+            Self::SelfAdaptive(Strategy::PerGenome, 0.1),
+        ]
+    }
+
+    /// Every `chance` must be in `[0, 1]`, step sizes finite, `sigma`,
+    /// `scale` and `initial_sigma` positive, and `min..max` a finite,
+    /// non-empty range.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let chance = |chance: f32| (0.0..=1.0).contains(&chance);
+        let range = |min: f32, max: f32| min.is_finite() && max.is_finite() && min < max;
+        let valid = match *self {
+            Self::UniformPerturbation(c, coeff) => chance(c) && coeff.is_finite(),
+            Self::Gaussian(c, step) | Self::Cauchy(c, step) => {
+                chance(c) && step.is_finite() && step > 0.0
+            }
+            Self::UniformReset(c, min, max) => chance(c) && range(min, max),
+            Self::Polynomial(c, eta, min, max) => {
+                chance(c) && eta.is_finite() && eta >= 0.0 && range(min, max)
+            }
+            Self::SelfAdaptive(_, sigma) => sigma.is_finite() && sigma > 0.0,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidMutation(*self))
+        }
+    }
+
+    /// Replaces `chance`, for `Schedules::mutation_chance`.
+    pub fn with_chance(self, chance: f32) -> Self {
         match self {
-            Self::Gaussian(chance, coeff) => self.gaussian(rng, child, *chance, *coeff),
+            Self::UniformPerturbation(_, coeff) => Self::UniformPerturbation(chance, coeff),
+            Self::Gaussian(_, sigma) => Self::Gaussian(chance, sigma),
+            Self::Cauchy(_, scale) => Self::Cauchy(chance, scale),
+            Self::UniformReset(_, min, max) => Self::UniformReset(chance, min, max),
+            Self::Polynomial(_, eta, min, max) => Self::Polynomial(chance, eta, min, max),
+            Self::SelfAdaptive(..) => self,
         }
     }
 
-    fn gaussian(&self, rng: &mut dyn RngCore, child: &mut Chromosome, chance: f32, coeff: f32) {
-        assert!((0.0..=1.0).contains(&chance));
+    /// Replaces the step size, for `Schedules::mutation_coeff`.
+    pub fn with_coeff(self, coeff: f32) -> Self {
+        match self {
+            Self::UniformPerturbation(chance, _) => Self::UniformPerturbation(chance, coeff),
+            Self::Gaussian(chance, _) => Self::Gaussian(chance, coeff),
+            Self::Cauchy(chance, _) => Self::Cauchy(chance, coeff),
+            Self::SelfAdaptive(strategy, _) => Self::SelfAdaptive(strategy, coeff),
+            Self::UniformReset(..) | Self::Polynomial(..) => self,
+        }
+    }
+
+    pub fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        match *self {
+            Self::UniformPerturbation(chance, coeff) => {
+                self.uniform_perturbation(rng, child, chance, coeff)
+            }
+            Self::Gaussian(chance, sigma) => {
+                let normal = Normal::new(0.0, sigma).expect("checked by `Mutation::check`");
 
+                self.each(rng, child, chance, |rng, gene| gene + normal.sample(rng))
+            }
+            Self::Cauchy(chance, scale) => {
+                let cauchy = Cauchy::new(0.0, scale).expect("checked by `Mutation::check`");
+
+                self.each(rng, child, chance, |rng, gene| gene + cauchy.sample(rng))
+            }
+            Self::UniformReset(chance, min, max) => {
+                self.each(rng, child, chance, |rng, _| rng.gen_range(min..max))
+            }
+            Self::Polynomial(chance, eta, min, max) => {
+                self.each(rng, child, chance, |rng, gene| {
+                    polynomial(rng, gene, eta, min, max)
+                })
+            }
+            Self::SelfAdaptive(strategy, initial_sigma) => {
+                self.self_adaptive(rng, child, strategy, initial_sigma)
+            }
+        }
+    }
+
+    /// Uses the usual learning rates: `1 / sqrt(n)` for a single step size;
+    /// `1 / sqrt(2 n)` overall and `1 / sqrt(2 sqrt(n))` per gene for one
+    /// per gene.
+    fn self_adaptive(
+        &self,
+        rng: &mut dyn RngCore,
+        child: &mut Chromosome,
+        strategy: Strategy,
+        initial_sigma: f32,
+    ) {
+        let n = child.len().max(1) as f32;
+        let len = match strategy {
+            Strategy::PerGenome => 1,
+            Strategy::PerGene => child.len(),
+        };
+
+        if child.strategy().len() != len {
+            *child.strategy_mut() = vec![initial_sigma; len];
+        }
+
+        let (overall, per_gene) = match strategy {
+            Strategy::PerGenome => (1.0 / n.sqrt(), 0.0),
+            Strategy::PerGene => (1.0 / (2.0 * n).sqrt(), 1.0 / (2.0 * n.sqrt()).sqrt()),
+        };
+        let common = overall * rng.sample::<f32, _>(StandardNormal);
+
+        for sigma in child.strategy_mut() {
+            let own = per_gene * rng.sample::<f32, _>(StandardNormal);
+
+            *sigma = (*sigma * (common + own).exp()).max(MIN_SIGMA);
+        }
+
+        let sigmas = child.strategy().to_vec();
+
+        for (idx, gene) in child.iter_mut().enumerate() {
+            let sigma = sigmas[idx.min(sigmas.len() - 1)];
+
+            *gene += sigma * rng.sample::<f32, _>(StandardNormal);
+        }
+    }
+
+    fn uniform_perturbation(
+        &self,
+        rng: &mut dyn RngCore,
+        child: &mut Chromosome,
+        chance: f32,
+        coeff: f32,
+    ) {
         child.iter_mut().for_each(|gene| {
             let sign = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
 
@@ -26,6 +198,41 @@ impl Mutation {
             }
         })
     }
+
+    fn each(
+        &self,
+        rng: &mut dyn RngCore,
+        child: &mut Chromosome,
+        chance: f32,
+        mut mutate: impl FnMut(&mut dyn RngCore, f32) -> f32,
+    ) {
+        child.iter_mut().for_each(|gene| {
+            if rng.gen_bool(chance as _) {
+                *gene = mutate(rng, *gene);
+            }
+        })
+    }
+}
+
+fn polynomial(rng: &mut dyn RngCore, gene: f32, eta: f32, min: f32, max: f32) -> f32 {
+    let gene = gene.clamp(min, max);
+    let range = max - min;
+    let exponent = 1.0 / (eta + 1.0);
+    let u: f32 = rng.gen();
+
+    let delta = if u < 0.5 {
+        let below = 1.0 - (gene - min) / range;
+        let value = 2.0 * u + (1.0 - 2.0 * u) * below.powf(eta + 1.0);
+
+        value.powf(exponent) - 1.0
+    } else {
+        let above = 1.0 - (max - gene) / range;
+        let value = 2.0 * (1.0 - u) + 2.0 * (u - 0.5) * above.powf(eta + 1.0);
+
+        1.0 - value.powf(exponent)
+    };
+
+    (gene + delta * range).clamp(min, max)
 }
 
 #[cfg(test)]
@@ -38,7 +245,7 @@ mod test {
         let mut child = vec![1.0, 2.0, 3.0, 4.0, 5.0].into_iter().collect();
         let mut rng = ChaCha8Rng::from_seed(Default::default());
 
-        Mutation::Gaussian(chance, coeff).mutate(&mut rng, &mut child);
+        Mutation::UniformPerturbation(chance, coeff).mutate(&mut rng, &mut child);
 
         child.into_iter().collect()
     }
@@ -126,4 +333,134 @@ mod test {
             }
         }
     }
+
+    fn mutated(method: Mutation) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut child: Chromosome = std::iter::repeat_n(0.5, 10_000).collect();
+
+        method.mutate(&mut rng, &mut child);
+        child.into_iter().collect()
+    }
+
+    #[test]
+    fn test_gaussian() {
+        let actual = mutated(Mutation::Gaussian(1.0, 0.2));
+        let mean = actual.iter().sum::<f32>() / actual.len() as f32;
+        let variance = actual.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / actual.len() as f32;
+
+        approx::assert_relative_eq!(mean, 0.5, epsilon = 0.01);
+        approx::assert_relative_eq!(variance.sqrt(), 0.2, epsilon = 0.01);
+        assert!(actual.iter().any(|x| (x - 0.5).abs() > 0.5));
+    }
+
+    #[test]
+    fn test_cauchy() {
+        let actual = mutated(Mutation::Cauchy(1.0, 0.1));
+        let mut deviations: Vec<_> = actual.iter().map(|x| (x - 0.5).abs()).collect();
+
+        deviations.sort_by(f32::total_cmp);
+
+        // The median deviation is the scale, and the tails are far heavier
+        // than a normal's.
+        approx::assert_relative_eq!(deviations[5_000], 0.1, epsilon = 0.01);
+        assert!(deviations[9_999] > 10.0);
+    }
+
+    #[test]
+    fn test_uniform_reset() {
+        let actual = mutated(Mutation::UniformReset(0.5, -2.0, -1.0));
+        let reset = actual.iter().filter(|&&x| x != 0.5).count();
+
+        assert!(actual
+            .iter()
+            .all(|&x| x == 0.5 || (-2.0..-1.0).contains(&x)));
+        assert!((4_800..5_200).contains(&reset), "{reset}");
+    }
+
+    #[test]
+    fn test_polynomial() {
+        for eta in [0.0, 5.0, 100.0] {
+            let actual = mutated(Mutation::Polynomial(1.0, eta, 0.0, 1.0));
+            let mean_step =
+                actual.iter().map(|x| (x - 0.5).abs()).sum::<f32>() / actual.len() as f32;
+
+            assert!(actual.iter().all(|x| (0.0..=1.0).contains(x)));
+            assert!(
+                (eta == 100.0 && mean_step < 0.01) || (eta < 100.0 && mean_step > 0.01),
+                "{eta}: {mean_step}"
+            );
+        }
+
+        // Out-of-bounds genes are brought back in.
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut child: Chromosome = vec![-10.0, 10.0].into_iter().collect();
+
+        Mutation::Polynomial(0.0, 20.0, -1.0, 1.0).mutate(&mut rng, &mut child);
+        Mutation::Polynomial(1.0, 20.0, -1.0, 1.0).mutate(&mut rng, &mut child);
+
+        assert!(child.iter().all(|x| (-1.0..=1.0).contains(x)));
+    }
+
+    #[test]
+    fn test_self_adaptive() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for (strategy, len) in [(Strategy::PerGenome, 1), (Strategy::PerGene, 100)] {
+            let method = Mutation::SelfAdaptive(strategy, 0.1);
+            let mut child: Chromosome = std::iter::repeat_n(0.0, 100).collect();
+
+            method.mutate(&mut rng, &mut child);
+
+            let sigmas = child.strategy().to_vec();
+
+            // Step sizes are set up, then mutated before being used.
+            assert_eq!(sigmas.len(), len);
+            assert!(sigmas.iter().all(|&sigma| sigma > 0.0 && sigma != 0.1));
+            assert!(child.iter().all(|&gene| gene != 0.0));
+
+            // Later on, they evolve from where they are.
+            method.mutate(&mut rng, &mut child);
+
+            assert_eq!(child.strategy().len(), len);
+            assert!(child
+                .strategy()
+                .iter()
+                .zip(&sigmas)
+                .all(|(after, before)| after != before && (after / before).ln().abs() < 2.0));
+        }
+    }
+
+    #[test]
+    fn test_self_adaptive_min_sigma() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut child = std::iter::repeat_n(0.0, 4)
+            .collect::<Chromosome>()
+            .with_strategy(vec![0.0]);
+
+        Mutation::SelfAdaptive(Strategy::PerGenome, 0.1).mutate(&mut rng, &mut child);
+
+        assert_eq!(child.strategy(), [MIN_SIGMA]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for method in [
+            Mutation::UniformPerturbation(1.5, 0.3),
+            Mutation::Gaussian(0.1, 0.0),
+            Mutation::Cauchy(0.1, f32::NAN),
+            Mutation::UniformReset(0.1, 1.0, -1.0),
+            Mutation::UniformReset(0.1, -1.0, f32::INFINITY),
+            Mutation::Polynomial(0.1, -1.0, -5.0, 5.0),
+            Mutation::SelfAdaptive(Strategy::PerGene, 0.0),
+        ] {
+            assert!(
+                matches!(method.check(), Err(NetworkError::InvalidMutation(_))),
+                "{method:?}"
+            );
+        }
+
+        for method in Mutation::presets() {
+            assert!(method.check().is_ok(), "{method:?}");
+        }
+    }
 }
@@ -0,0 +1,236 @@
+use crate::*;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Transforms fitness before `Selection` sees it. Apart from `Raw`, every
+/// method yields non-negative values; fitness-proportionate selection
+/// treats negative `Raw` fitness as if it were `Offset`. A population that
+/// ends up all zero is picked from uniformly.
+#[derive(
+    IntoStaticStr, EnumIter, Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize,
+)]
+pub enum Scaling {
+    #[default]
+    Raw,
+    /// Shifts fitness so that the least fit is at zero.
+    Offset,
+    /// Linear map of the offset fitness that keeps the average and takes
+    /// the fittest to `multiple` times it, or as close as it gets without
+    /// going below zero.
+    /// (multiple)
+    Linear(f32),
+    /// `fitness - (average - c * stddev)`, clamped at zero, which ignores
+    /// everyone more than `c` deviations below the average.
+    /// (c)
+    SigmaTruncation(f32),
+    /// Rank over population size, ranking the least fit as 1.
+    Rank,
+    /// `exp(fitness / temperature)`, with the temperature cooling from
+    /// `initial` by `decay` every generation, down to `min`; the lower it
+    /// is, the more the fittest dominate. `Schedules::temperature` allows
+    /// other schedules.
+    /// (initial, decay, min)
+    Boltzmann(f32, f32, f32),
+}
+
+impl Scaling {
+    /// Fixes the temperature of `Boltzmann`, for `Schedules::temperature`.
+    pub fn with_temperature(self, temperature: f32) -> Self {
+        match self {
+            Self::Boltzmann(..) => Self::Boltzmann(temperature, 1.0, temperature),
+            _ => self,
+        }
+    }
+
+    /// `Linear` needs a multiple of at least 1, and `Boltzmann` a positive
+    /// minimum temperature that `initial` starts at or above, cooling by a
+    /// `decay` in `[0, 1]`.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let valid = match *self {
+            Self::Raw | Self::Offset | Self::Rank => true,
+            Self::Linear(multiple) => multiple.is_finite() && multiple >= 1.0,
+            Self::SigmaTruncation(c) => c.is_finite(),
+            Self::Boltzmann(initial, decay, min) => {
+                initial.is_finite() && min > 0.0 && initial >= min && (0.0..=1.0).contains(&decay)
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidScaling(*self))
+        }
+    }
+
+    pub fn apply(&self, fitness: &[f32], generation: usize) -> Vec<f32> {
+        if fitness.is_empty() {
+            return Vec::new();
+        }
+
+        let n = fitness.len() as f32;
+        let min = fitness.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = fitness.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let avg = fitness.iter().sum::<f32>() / n;
+
+        match *self {
+            Self::Raw => fitness.to_vec(),
+
+            Self::Offset => fitness.iter().map(|f| f - min).collect(),
+
+            Self::Linear(multiple) => {
+                let (avg, max) = (avg - min, max - min);
+
+                if max <= avg {
+                    return vec![0.0; fitness.len()];
+                }
+
+                // Keeping the average fixes `b = avg * (1 - a)`, which is
+                // negative, and would make the least fit so, for `a > 1`.
+                let a = ((multiple - 1.0) * avg / (max - avg)).min(1.0);
+                let b = avg * (1.0 - a);
+
+                fitness
+                    .iter()
+                    .map(|f| (a * (f - min) + b).max(0.0))
+                    .collect()
+            }
+
+            Self::SigmaTruncation(c) => {
+                let stddev = (fitness.iter().map(|f| (f - avg).powi(2)).sum::<f32>() / n).sqrt();
+                let threshold = avg - c * stddev;
+
+                fitness.iter().map(|f| (f - threshold).max(0.0)).collect()
+            }
+
+            Self::Rank => selection::ranks(fitness)
+                .into_iter()
+                .map(|rank| rank / n)
+                .collect(),
+
+            Self::Boltzmann(initial, decay, min_temperature) => {
+                let temperature = self::temperature(initial, decay, min_temperature, generation);
+
+                // Relative to the fittest, so that `exp` can't overflow.
+                fitness
+                    .iter()
+                    .map(|f| ((f - max) / temperature).exp())
+                    .collect()
+            }
+        }
+    }
+}
+
+fn temperature(initial: f32, decay: f32, min: f32, generation: usize) -> f32 {
+    Schedule::Exponential(initial, decay, min).value(generation, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const FITNESS: [f32; 4] = [-2.0, 0.0, 1.0, 5.0];
+
+    #[test]
+    fn test_offset() {
+        assert_relative_eq!(
+            Scaling::Offset.apply(&FITNESS, 0).as_slice(),
+            [0.0, 2.0, 3.0, 7.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_linear() {
+        // Offset: [0, 2, 3, 7], average 3.
+        let actual = Scaling::Linear(1.5).apply(&FITNESS, 0);
+
+        assert_relative_eq!(actual.iter().sum::<f32>() / 4.0, 3.0);
+        assert_relative_eq!(actual[3], 4.5);
+
+        // Too steep to keep everyone above zero, so it's the offset.
+        assert_relative_eq!(
+            Scaling::Linear(10.0).apply(&FITNESS, 0).as_slice(),
+            [0.0, 2.0, 3.0, 7.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_sigma_truncation() {
+        // Average 1, standard deviation 1.
+        let fitness = [0.0, 0.0, 2.0, 2.0];
+
+        assert_relative_eq!(
+            Scaling::SigmaTruncation(2.0).apply(&fitness, 0).as_slice(),
+            [1.0, 1.0, 3.0, 3.0].as_slice()
+        );
+        assert_relative_eq!(
+            Scaling::SigmaTruncation(0.0).apply(&fitness, 0).as_slice(),
+            [0.0, 0.0, 1.0, 1.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        assert_relative_eq!(
+            Scaling::Rank.apply(&[3.0, -1.0, 3.0, 0.5], 0).as_slice(),
+            [0.75, 0.25, 0.75, 0.5].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_boltzmann() {
+        let method = Scaling::Boltzmann(4.0, 0.5, 1.0);
+
+        assert_relative_eq!(
+            method.apply(&[1.0, 5.0], 0).as_slice(),
+            [(-1.0f32).exp(), 1.0].as_slice()
+        );
+        assert_relative_eq!(
+            method.apply(&[1.0, 5.0], 1).as_slice(),
+            [(-2.0f32).exp(), 1.0].as_slice()
+        );
+        assert_relative_eq!(
+            method.apply(&[1.0, 5.0], 10).as_slice(),
+            [(-4.0f32).exp(), 1.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for method in [
+            Scaling::Linear(0.5),
+            Scaling::SigmaTruncation(f32::NAN),
+            Scaling::Boltzmann(1.0, 0.5, 0.0),
+            Scaling::Boltzmann(0.5, 0.5, 1.0),
+            Scaling::Boltzmann(4.0, 2.0, 1.0),
+        ] {
+            assert!(
+                matches!(method.check(), Err(NetworkError::InvalidScaling(_))),
+                "{method:?}"
+            );
+        }
+
+        assert!(Scaling::Boltzmann(4.0, 0.5, 1.0).check().is_ok());
+    }
+
+    #[test]
+    fn test_all_zero() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let fitness = [0.0; 4];
+
+        for method in [Scaling::Raw, Scaling::Offset, Scaling::Linear(2.0)] {
+            let scaled = method.apply(&fitness, 0);
+
+            for selection in [Selection::Roulette, Selection::StochasticUniversal] {
+                let picks = selection.pick(&mut rng, &scaled, 400);
+
+                for idx in 0..4 {
+                    let count = picks.iter().filter(|&&pick| pick == idx).count();
+
+                    assert!((70..130).contains(&count), "{method:?} {selection:?}");
+                }
+            }
+        }
+    }
+}
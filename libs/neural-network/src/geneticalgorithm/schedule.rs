@@ -0,0 +1,337 @@
+use crate::*;
+use std::f32::consts::PI;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// A parameter that changes over the generations, see `Schedules`.
+#[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Schedule {
+    /// (value)
+    Constant(f32),
+    /// Moves from `start` to `end` over `generations`, then stays there.
+    /// (start, end, generations)
+    Linear(f32, f32, usize),
+    /// Multiplied by `decay` every generation, down to `min`.
+    /// (initial, decay, min)
+    Exponential(f32, f32, f32),
+    /// Multiplied by `factor` every `every` generations.
+    /// (initial, factor, every)
+    Step(f32, f32, usize),
+    /// Half a cosine from `start` to `end` over `generations`, then stays
+    /// there; changes slowly at both ends and fastest halfway.
+    /// (start, end, generations)
+    Cosine(f32, f32, usize),
+    /// Multiplied by `factor` for every `patience` generations that the
+    /// best fitness hasn't improved, up to `limit` (or down to it, for
+    /// `factor < 1`), and back to `initial` once it does.
+    /// (initial, factor, patience, limit)
+    Adaptive(f32, f32, usize, f32),
+}
+
+impl Schedule {
+    /// Values must be finite, `Exponential` decay in `[0, 1]`, and `Step`
+    /// and `Adaptive` periods at least a generation long.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let valid = match *self {
+            Self::Constant(value) => value.is_finite(),
+            Self::Linear(start, end, _) | Self::Cosine(start, end, _) => {
+                start.is_finite() && end.is_finite()
+            }
+            Self::Exponential(initial, decay, min) => {
+                initial.is_finite() && min.is_finite() && (0.0..=1.0).contains(&decay)
+            }
+            Self::Step(initial, factor, every) => {
+                initial.is_finite() && factor.is_finite() && every > 0
+            }
+            Self::Adaptive(initial, factor, patience, limit) => {
+                initial.is_finite() && factor.is_finite() && limit.is_finite() && patience > 0
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidSchedule(*self))
+        }
+    }
+
+    /// `stagnation` counts the generations since the best fitness last
+    /// improved.
+    pub fn value(&self, generation: usize, stagnation: usize) -> f32 {
+        match *self {
+            Self::Constant(value) => value,
+
+            Self::Linear(start, end, generations) => {
+                start + (end - start) * progress(generation, generations)
+            }
+
+            Self::Exponential(initial, decay, min) => {
+                (initial * decay.powi(generation as i32)).max(min)
+            }
+
+            Self::Step(initial, factor, every) => {
+                initial * factor.powi((generation / every) as i32)
+            }
+
+            Self::Cosine(start, end, generations) => {
+                let t = progress(generation, generations);
+
+                end + (start - end) * 0.5 * (1.0 + (PI * t).cos())
+            }
+
+            Self::Adaptive(initial, factor, patience, limit) => {
+                let value = initial * factor.powi((stagnation / patience) as i32);
+
+                if factor >= 1.0 {
+                    value.min(limit)
+                } else {
+                    value.max(limit)
+                }
+            }
+        }
+    }
+}
+
+fn progress(generation: usize, generations: usize) -> f32 {
+    if generations == 0 {
+        1.0
+    } else {
+        (generation as f32 / generations as f32).min(1.0)
+    }
+}
+
+/// Floor of scheduled step sizes and temperatures, which must be positive.
+pub(crate) const MIN_SCHEDULED: f32 = 1e-6;
+
+/// Overrides for the parameters of `GeneticAlgorithm`'s methods; `None`
+/// keeps the method's own.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Schedules {
+    /// `chance` of every `Mutation` that has one; clamped to `0..=1`.
+    pub mutation_chance: Option<Schedule>,
+    /// The step size of `Mutation`: `coeff`, `sigma`, `scale` or
+    /// `initial_sigma`, kept positive. `UniformReset` and `Polynomial` have
+    /// none.
+    pub mutation_coeff: Option<Schedule>,
+    /// Rounded, and at least 1; only used by `Selection::Tournament`.
+    pub tournament_size: Option<Schedule>,
+    /// Replaces the cooling built into `Scaling::Boltzmann`; kept positive.
+    pub temperature: Option<Schedule>,
+}
+
+impl Schedules {
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        [
+            &self.mutation_chance,
+            &self.mutation_coeff,
+            &self.tournament_size,
+            &self.temperature,
+        ]
+        .into_iter()
+        .flatten()
+        .try_for_each(Schedule::check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn values(schedule: Schedule, generations: impl IntoIterator<Item = usize>) -> Vec<f32> {
+        generations
+            .into_iter()
+            .map(|generation| schedule.value(generation, 0))
+            .collect()
+    }
+
+    #[test]
+    fn test_constant() {
+        assert_eq!(values(Schedule::Constant(0.5), [0, 10, 1000]), [0.5; 3]);
+    }
+
+    #[test]
+    fn test_linear() {
+        assert_relative_eq!(
+            values(Schedule::Linear(1.0, 0.0, 4), [0, 1, 2, 4, 8]).as_slice(),
+            [1.0, 0.75, 0.5, 0.0, 0.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_exponential() {
+        assert_relative_eq!(
+            values(Schedule::Exponential(1.0, 0.5, 0.2), [0, 1, 2, 3]).as_slice(),
+            [1.0, 0.5, 0.25, 0.2].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        assert_relative_eq!(
+            values(Schedule::Step(1.0, 0.1, 10), [0, 9, 10, 25]).as_slice(),
+            [1.0, 1.0, 0.1, 0.01].as_slice()
+        );
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert_relative_eq!(
+            values(Schedule::Cosine(1.0, 0.0, 4), [0, 2, 4, 5]).as_slice(),
+            [1.0, 0.5, 0.0, 0.0].as_slice(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let schedule = Schedule::Adaptive(0.1, 2.0, 3, 0.5);
+        let actual: Vec<_> = [0, 2, 3, 6, 30]
+            .into_iter()
+            .map(|stagnation| schedule.value(100, stagnation))
+            .collect();
+
+        assert_relative_eq!(actual.as_slice(), [0.1, 0.1, 0.2, 0.4, 0.5].as_slice());
+    }
+
+    #[test]
+    fn test_genetic_algorithm() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut ga = GeneticAlgorithm::new(
+            Selection::Tournament(2),
+            Crossover::Uniform,
+            Mutation::Gaussian(0.5, 0.5),
+        )
+        .with_scaling(Scaling::Boltzmann(1.0, 0.9, 0.1))
+        .with_schedules(Schedules {
+            mutation_chance: Some(Schedule::Constant(0.0)),
+            mutation_coeff: Some(Schedule::Linear(1.0, 0.0, 4)),
+            tournament_size: Some(Schedule::Step(4.0, 0.5, 1)),
+            temperature: Some(Schedule::Adaptive(1.0, 2.0, 1, 8.0)),
+        });
+
+        assert!(matches!(
+            ga.scheduled(),
+            (
+                Selection::Tournament(4),
+                Scaling::Boltzmann(1.0, _, _),
+                Mutation::Gaussian(0.0, 1.0)
+            )
+        ));
+
+        let mut population: Vec<_> = (0..4)
+            .map(|_| TestIndividual::create([0.0, 0.0].into_iter().collect()))
+            .collect();
+
+        // Nothing mutates, so the best fitness never improves.
+        for _ in 0..3 {
+            population = ga.evolve(&mut rng, &population).0;
+        }
+
+        assert_eq!(ga.stagnation(), 2);
+        assert!(matches!(
+            ga.scheduled(),
+            (
+                Selection::Tournament(1),
+                Scaling::Boltzmann(4.0, _, _),
+                Mutation::Gaussian(0.0, 0.25)
+            )
+        ));
+    }
+
+    /// Schedules that run out of range are clamped rather than reaching
+    /// the methods' assertions.
+    #[test]
+    fn test_out_of_range() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for mutation in [
+            Mutation::Gaussian(0.5, 0.5),
+            Mutation::Cauchy(0.5, 0.5),
+            Mutation::SelfAdaptive(Strategy::PerGene, 0.5),
+        ] {
+            for mutation_chance in [Schedule::Linear(0.5, 0.0, 2), Schedule::Step(0.5, 4.0, 1)] {
+                let mut ga =
+                    GeneticAlgorithm::new(Selection::Roulette, Crossover::Uniform, mutation)
+                        .with_scaling(Scaling::Boltzmann(1.0, 0.9, 0.1))
+                        .with_schedules(Schedules {
+                            mutation_chance: Some(mutation_chance),
+                            mutation_coeff: Some(Schedule::Linear(0.5, 0.0, 2)),
+                            tournament_size: None,
+                            temperature: Some(Schedule::Linear(1.0, 0.0, 2)),
+                        });
+
+                let mut population: Vec<_> = (0..4)
+                    .map(|_| TestIndividual::create([0.0, 1.0].into_iter().collect()))
+                    .collect();
+
+                for _ in 0..4 {
+                    population = ga.evolve(&mut rng, &population).0;
+                }
+
+                assert!(matches!(
+                    ga.scheduled(),
+                    (_, Scaling::Boltzmann(MIN_SCHEDULED, _, _), _)
+                ));
+            }
+        }
+    }
+
+    /// Steps that overflow, to infinity or to `0 * inf`, are still kept
+    /// within range.
+    #[test]
+    fn test_overflow() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut ga = GeneticAlgorithm::new(
+            Selection::Tournament(2),
+            Crossover::Uniform,
+            Mutation::Gaussian(0.5, 0.5),
+        )
+        .with_scaling(Scaling::Boltzmann(1.0, 0.9, 0.1))
+        .with_schedules(Schedules {
+            mutation_chance: Some(Schedule::Step(0.0, 1e30, 1)),
+            mutation_coeff: Some(Schedule::Step(1.0, 1e30, 1)),
+            tournament_size: Some(Schedule::Step(0.0, 1e30, 1)),
+            temperature: Some(Schedule::Step(1.0, 1e30, 1)),
+        });
+        let mut population: Vec<_> = (0..4)
+            .map(|_| TestIndividual::create([0.0, 1.0].into_iter().collect()))
+            .collect();
+
+        assert!(ga.check().is_ok());
+
+        for _ in 0..4 {
+            population = ga.evolve(&mut rng, &population).0;
+        }
+
+        assert!(matches!(
+            ga.scheduled(),
+            (
+                Selection::Tournament(1),
+                _,
+                Mutation::Gaussian(0.0, f32::MAX)
+            )
+        ));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for schedule in [
+            Schedule::Constant(f32::NAN),
+            Schedule::Exponential(1.0, 1.5, 0.1),
+            Schedule::Step(1.0, 0.5, 0),
+            Schedule::Adaptive(1.0, 2.0, 0, 4.0),
+        ] {
+            let schedules = Schedules {
+                temperature: Some(schedule),
+                ..Default::default()
+            };
+
+            assert!(
+                matches!(schedules.check(), Err(NetworkError::InvalidSchedule(_))),
+                "{schedule:?}"
+            );
+        }
+    }
+}
@@ -1,80 +1,230 @@
 use crate::*;
 use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom};
+use std::borrow::Cow;
 use strum_macros::{EnumIter, IntoStaticStr};
 
 #[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Selection {
-    Rank,
+    /// Proportional to `rank ^ pressure`, ranking the least fit as 1; a
+    /// pressure of 0 picks uniformly and 1 linearly.
+    /// (pressure)
+    Rank(f32),
     Roulette,
-    Tournament,
+    /// Fittest of `size` individuals drawn with replacement.
+    /// (size)
+    Tournament(usize),
+    /// Roulette with evenly spaced pointers, so that over a whole
+    /// generation everyone is picked within one of their expected count.
+    StochasticUniversal,
+    /// Uniformly among the fittest `fraction` of the population.
+    /// (fraction)
+    Truncation(f32),
 }
 
 impl Selection {
+    /// Every method with sensible parameters, e.g. for frontends that only
+    /// let users pick by name.
+    pub fn presets() -> [Self; 5] {
+        [
+            Self::Rank(1.0),
+            Self::Roulette,
+            Self::Tournament(3),
+            Self::StochasticUniversal,
+            Self::Truncation(0.3),
+        ]
+    }
+
+    /// Pressure must be finite and non-negative, tournaments hold at least
+    /// one, and truncation keeps a fraction in `(0, 1]`.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let valid = match *self {
+            Self::Rank(pressure) => pressure.is_finite() && pressure >= 0.0,
+            Self::Tournament(size) => size > 0,
+            Self::Truncation(fraction) => fraction > 0.0 && fraction <= 1.0,
+            Self::Roulette | Self::StochasticUniversal => true,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidSelection(*self))
+        }
+    }
+
+    /// Replaces the size of `Tournament`, for `Schedules::tournament_size`.
+    pub fn with_tournament_size(self, size: usize) -> Self {
+        match self {
+            Self::Tournament(_) => Self::Tournament(size),
+            _ => self,
+        }
+    }
+
     pub fn select<'a, T>(&self, rng: &mut dyn RngCore, population: &'a [T]) -> &'a T
     where
         T: Individual,
     {
-        match self {
-            Self::Rank => self.rank_select(rng, population),
-            Self::Roulette => self.roulette_select(rng, population),
-            Self::Tournament => self.tournament_select(rng, population),
-        }
+        &population[self.pick(rng, &fitness(population), 1)[0]]
     }
 
-    fn rank_select<'a, T>(&self, rng: &mut dyn RngCore, population: &'a [T]) -> &'a T
+    /// Picks `count` parents at once, which `StochasticUniversal` and
+    /// `Truncation` need to be any better than the single pick `select`.
+    pub fn select_many<'a, T>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &'a [T],
+        count: usize,
+    ) -> Vec<&'a T>
     where
         T: Individual,
     {
-        let n = population.len();
-        let mut ranks: Vec<f32> = Vec::with_capacity(n);
-        let rank_sum: f32 = n as f32 * (n as f32 + 1.0) / 2.0;
+        self.pick(rng, &fitness(population), count)
+            .into_iter()
+            .map(|idx| &population[idx])
+            .collect()
+    }
 
-        for individual in population {
-            let mut rank = 1.0;
+    /// Indices of `count` picks, by `fitness` rather than the individuals'
+    /// own, e.g. after a `Scaling`.
+    ///
+    /// `Roulette` and `StochasticUniversal` use the fitness as weights.
+    /// Negative fitness is shifted as by `Scaling::Offset` first; when the
+    /// weights are all zero, they pick uniformly.
+    pub fn pick(&self, rng: &mut dyn RngCore, fitness: &[f32], count: usize) -> Vec<usize> {
+        assert!(!fitness.is_empty(), "empty pop");
 
-            for comp in population {
-                if individual.fitness() > comp.fitness() {
-                    rank += 1.0
-                }
-            }
+        match self {
+            Self::Rank(pressure) => {
+                let dist = WeightedIndex::new(rank_weights(fitness, *pressure))
+                    .expect("checked by `Selection::check`");
 
-            ranks.push(rank / rank_sum)
+                (0..count).map(|_| dist.sample(rng)).collect()
+            }
+            Self::Roulette => match roulette_weights(fitness) {
+                Some(dist) => (0..count).map(|_| dist.sample(rng)).collect(),
+                None => uniform(rng, fitness.len(), count),
+            },
+            Self::Tournament(size) => (0..count)
+                .map(|_| tournament(rng, fitness, *size))
+                .collect(),
+            Self::StochasticUniversal => universal(rng, fitness, count),
+            Self::Truncation(fraction) => {
+                let fittest = truncate(fitness, *fraction);
+
+                (0..count).map(|_| *fittest.choose(rng).unwrap()).collect()
+            }
         }
+    }
+}
+
+fn fitness<T>(population: &[T]) -> Vec<f32>
+where
+    T: Individual,
+{
+    population
+        .iter()
+        .map(|individual| individual.fitness())
+        .collect()
+}
+
+fn rank_weights(fitness: &[f32], pressure: f32) -> Vec<f32> {
+    let n = fitness.len() as f32;
+
+    // Relative to the fittest, whose weight stays at 1, so that high
+    // pressure can't underflow every weight to zero.
+    ranks(fitness)
+        .into_iter()
+        .map(|rank| (rank / n).powf(pressure))
+        .collect()
+}
+
+/// 1 for the least fit, `n` for the fittest; ties share the lowest rank.
+pub(crate) fn ranks(fitness: &[f32]) -> Vec<f32> {
+    fitness
+        .iter()
+        .map(|&f| 1.0 + fitness.iter().filter(|&&comp| f > comp).count() as f32)
+        .collect()
+}
+
+/// `None` when every weight is zero.
+fn roulette_weights(fitness: &[f32]) -> Option<WeightedIndex<f32>> {
+    let weights = proportionate_weights(fitness);
+
+    weights
+        .iter()
+        .any(|&w| w > 0.0)
+        .then(|| WeightedIndex::new(&*weights).unwrap())
+}
 
-        let dist = WeightedIndex::new(ranks).unwrap();
+/// `fitness` as is, unless some is negative, in which case the least fit
+/// are shifted to zero.
+fn proportionate_weights(fitness: &[f32]) -> Cow<'_, [f32]> {
+    let min = fitness.iter().copied().fold(f32::INFINITY, f32::min);
 
-        &population[dist.sample(rng)]
+    if min < 0.0 {
+        Cow::Owned(fitness.iter().map(|f| f - min).collect())
+    } else {
+        Cow::Borrowed(fitness)
     }
+}
 
-    fn roulette_select<'a, T>(&self, rng: &mut dyn RngCore, population: &'a [T]) -> &'a T
-    where
-        T: Individual,
-    {
-        population
-            .choose_weighted(rng, |individual| individual.fitness())
-            .expect("empty pop")
+fn uniform(rng: &mut dyn RngCore, len: usize, count: usize) -> Vec<usize> {
+    (0..count).map(|_| rng.gen_range(0..len)).collect()
+}
+
+/// Fittest of `size` draws with replacement.
+fn tournament(rng: &mut dyn RngCore, fitness: &[f32], size: usize) -> usize {
+    (0..size)
+        .map(|_| rng.gen_range(0..fitness.len()))
+        .reduce(|best, idx| {
+            if fitness[idx] > fitness[best] {
+                idx
+            } else {
+                best
+            }
+        })
+        .expect("checked by `Selection::check`")
+}
+
+/// The picks come out shuffled, so that consecutive ones can be paired up.
+fn universal(rng: &mut dyn RngCore, fitness: &[f32], count: usize) -> Vec<usize> {
+    let fitness = &*proportionate_weights(fitness);
+    let total: f32 = fitness.iter().sum();
+
+    if total <= 0.0 {
+        return uniform(rng, fitness.len(), count);
     }
 
-    fn tournament_select<'a, T>(&self, rng: &mut dyn RngCore, population: &'a [T]) -> &'a T
-    where
-        T: Individual,
-    {
-        let n = population.len();
-        let tournament_size = rng.gen_range(0..n);
-        let mut selected = &population[rng.gen_range(0..n)];
-        let mut best = 0.0;
+    let spacing = total / count as f32;
+    let mut pointer = rng.gen_range(0.0..spacing);
+    let mut cumulative = 0.0;
+    let mut selected = Vec::with_capacity(count);
 
-        for _ in 0..tournament_size {
-            let p = &population[rng.gen_range(0..n)];
+    for (idx, &f) in fitness.iter().enumerate() {
+        cumulative += f;
 
-            if p.fitness() >= best {
-                best = p.fitness();
-                selected = p;
-            }
+        while pointer < cumulative && selected.len() < count {
+            selected.push(idx);
+            pointer += spacing;
         }
+    }
 
-        selected
+    // Rounding can leave the last pointer just past the end.
+    while selected.len() < count {
+        selected.push(fitness.len() - 1);
     }
+
+    selected.shuffle(rng);
+    selected
+}
+
+/// Indices of the fittest `fraction`, at least one.
+fn truncate(fitness: &[f32], fraction: f32) -> Vec<usize> {
+    let mut order: Vec<_> = (0..fitness.len()).collect();
+    let len = ((fitness.len() as f32 * fraction).ceil() as usize).max(1);
+
+    order.sort_by(|&a, &b| fitness[b].total_cmp(&fitness[a]));
+    order.truncate(len);
+    order
 }
 
 #[cfg(test)]
@@ -87,7 +237,7 @@ mod test {
     #[test]
     fn test_rank() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
-        let method = Selection::Rank;
+        let method = Selection::Rank(1.0);
 
         let population = vec![
             TestIndividual::new(1.0),
@@ -137,7 +287,7 @@ mod test {
     #[test]
     fn test_tournament() {
         let mut rng = ChaCha8Rng::from_seed(Default::default());
-        let method = Selection::Tournament;
+        let method = Selection::Tournament(2);
 
         let population = vec![
             TestIndividual::new(1.0),
@@ -154,8 +304,152 @@ mod test {
                 histogram
             });
 
-        let expected_histogram = BTreeMap::from_iter(vec![(1, 142), (2, 215), (3, 275), (4, 368)]);
+        let expected_histogram = BTreeMap::from_iter(vec![(1, 60), (2, 192), (3, 321), (4, 427)]);
 
         assert_eq!(actual_histogram, expected_histogram);
     }
+
+    #[test]
+    fn test_tournament_negative_fitness() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = vec![TestIndividual::new(-3.0), TestIndividual::new(-1.0)];
+
+        let best = (0..100)
+            .map(|_| Selection::Tournament(8).select(&mut rng, &population))
+            .filter(|individual| individual.fitness() == -1.0)
+            .count();
+
+        assert!(best > 95, "{best}");
+    }
+
+    #[test]
+    fn test_proportionate_negative_fitness() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let fitness = [-3.0, -1.0, 1.0];
+
+        for method in [Selection::Roulette, Selection::StochasticUniversal] {
+            let picks = method.pick(&mut rng, &fitness, 1000);
+            let count = |idx| picks.iter().filter(|&&pick| pick == idx).count();
+
+            // Weighted as 0, 2 and 4.
+            assert_eq!(count(0), 0, "{method:?}");
+            assert!((300..370).contains(&count(1)), "{method:?}: {}", count(1));
+            assert!((630..700).contains(&count(2)), "{method:?}: {}", count(2));
+        }
+    }
+
+    #[test]
+    fn test_rank_pressure() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = vec![
+            TestIndividual::new(1.0),
+            TestIndividual::new(2.0),
+            TestIndividual::new(3.0),
+            TestIndividual::new(4.0),
+        ];
+        let worst = |pressure: f32, rng: &mut ChaCha8Rng| {
+            (0..1000)
+                .map(|_| Selection::Rank(pressure).select(rng, &population))
+                .filter(|individual| individual.fitness() == 1.0)
+                .count()
+        };
+
+        assert!((200..300).contains(&worst(0.0, &mut rng)));
+        assert!(worst(3.0, &mut rng) < 20);
+    }
+
+    #[test]
+    fn test_rank_high_pressure() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for (pressure, n) in [(40.0, 30), (20.0, 1000), (200.0, 1000)] {
+            let fitness: Vec<_> = (0..n).map(|f| f as f32).collect();
+            let picks = Selection::Rank(pressure).pick(&mut rng, &fitness, 100);
+
+            assert!(picks.iter().all(|&idx| idx >= n / 2), "{pressure} {n}");
+        }
+    }
+
+    #[test]
+    fn test_stochastic_universal() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = vec![
+            TestIndividual::new(1.0),
+            TestIndividual::new(2.0),
+            TestIndividual::new(3.0),
+            TestIndividual::new(4.0),
+        ];
+
+        for _ in 0..20 {
+            let selected = Selection::StochasticUniversal.select_many(&mut rng, &population, 20);
+
+            assert_eq!(selected.len(), 20);
+
+            // Everyone is picked within one of `20 * fitness / 10`.
+            for individual in &population {
+                let count = selected
+                    .iter()
+                    .filter(|selected| selected.fitness() == individual.fitness())
+                    .count() as f32;
+
+                assert!((count - 2.0 * individual.fitness()).abs() < 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncation() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population: Vec<_> = (0..10).map(|x| TestIndividual::new(x as f32)).collect();
+
+        let selected = Selection::Truncation(0.3).select_many(&mut rng, &population, 100);
+
+        assert!(selected
+            .iter()
+            .all(|individual| individual.fitness() >= 7.0));
+
+        for fitness in [7.0, 8.0, 9.0] {
+            assert!(selected
+                .iter()
+                .any(|individual| individual.fitness() == fitness));
+        }
+
+        assert_eq!(
+            Selection::Truncation(0.01)
+                .select(&mut rng, &population)
+                .fitness(),
+            9.0
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for method in [
+            Selection::Rank(-1.0),
+            Selection::Rank(f32::NAN),
+            Selection::Tournament(0),
+            Selection::Truncation(0.0),
+            Selection::Truncation(1.5),
+        ] {
+            assert!(
+                matches!(method.check(), Err(NetworkError::InvalidSelection(_))),
+                "{method:?}"
+            );
+        }
+
+        for method in Selection::presets() {
+            assert!(method.check().is_ok(), "{method:?}");
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&Selection::Tournament(4)).unwrap();
+
+        assert_eq!(json, "{\"Tournament\":4}");
+        assert!(matches!(
+            serde_json::from_str("\"StochasticUniversal\"").unwrap(),
+            Selection::StochasticUniversal
+        ));
+    }
 }
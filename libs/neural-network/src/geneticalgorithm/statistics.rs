@@ -1,9 +1,26 @@
 use crate::*;
 
+/// Describes the population handed to `evolve`, i.e. the generation that
+/// was just evaluated, not the offspring it returns.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Statistics {
     min_fitness: f32,
     max_fitness: f32,
     avg_fitness: f32,
+    median_fitness: f32,
+    stddev_fitness: f32,
+    lower_quartile_fitness: f32,
+    upper_quartile_fitness: f32,
+    best_index: usize,
+    diversity: Option<Diversity>,
+}
+
+/// How far apart the chromosomes are; the lower, the closer the population
+/// is to converging.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Diversity {
+    mean_distance: f32,
+    gene_variance: Vec<f32>,
 }
 
 impl Statistics {
@@ -11,24 +28,53 @@ impl Statistics {
     where
         I: Individual,
     {
-        assert!(!population.is_empty());
+        let mut statistics =
+            Self::from_fitness(population.iter().map(|individual| individual.fitness()));
 
-        let mut min_fitness = population[0].fitness();
-        let mut max_fitness = min_fitness;
-        let mut sum_fitness = 0.0;
+        statistics.diversity = Some(Diversity::new(
+            &population
+                .iter()
+                .map(|individual| individual.chromosome())
+                .collect::<Vec<_>>(),
+        ));
 
-        for individual in population {
-            let fitness = individual.fitness();
+        statistics
+    }
 
-            min_fitness = min_fitness.min(fitness);
-            max_fitness = max_fitness.max(fitness);
-            sum_fitness += fitness;
-        }
+    /// Without `diversity`, e.g. for genomes that aren't `Chromosome`s.
+    pub(crate) fn from_fitness(fitness: impl IntoIterator<Item = f32>) -> Self {
+        let fitness: Vec<_> = fitness.into_iter().collect();
+
+        assert!(!fitness.is_empty(), "population must not be empty");
+
+        let n = fitness.len() as f32;
+        let avg_fitness = fitness.iter().sum::<f32>() / n;
+        let variance = fitness
+            .iter()
+            .map(|f| (f - avg_fitness).powi(2))
+            .sum::<f32>()
+            / n;
+
+        // The first of equals.
+        let best_index =
+            fitness.iter().enumerate().fold(
+                0,
+                |best, (idx, f)| if *f > fitness[best] { idx } else { best },
+            );
+
+        let mut sorted = fitness.clone();
+        sorted.sort_by(f32::total_cmp);
 
         Self {
-            min_fitness,
-            max_fitness,
-            avg_fitness: sum_fitness / (population.len() as f32),
+            min_fitness: sorted[0],
+            max_fitness: sorted[sorted.len() - 1],
+            avg_fitness,
+            median_fitness: quantile(&sorted, 0.5),
+            stddev_fitness: variance.sqrt(),
+            lower_quartile_fitness: quantile(&sorted, 0.25),
+            upper_quartile_fitness: quantile(&sorted, 0.75),
+            best_index,
+            diversity: None,
         }
     }
 
@@ -43,4 +89,140 @@ impl Statistics {
     pub fn avg_fitness(&self) -> f32 {
         self.avg_fitness
     }
+
+    pub fn median_fitness(&self) -> f32 {
+        self.median_fitness
+    }
+
+    /// Population standard deviation.
+    pub fn stddev_fitness(&self) -> f32 {
+        self.stddev_fitness
+    }
+
+    /// First and third quartiles, interpolated between neighbours.
+    pub fn quartiles_fitness(&self) -> (f32, f32) {
+        (self.lower_quartile_fitness, self.upper_quartile_fitness)
+    }
+
+    /// Where the fittest individual is in the population.
+    pub fn best_index(&self) -> usize {
+        self.best_index
+    }
+
+    /// `None` for `Neat`, whose genomes differ in length.
+    pub fn diversity(&self) -> Option<&Diversity> {
+        self.diversity.as_ref()
+    }
+}
+
+impl Diversity {
+    fn new(chromosomes: &[&Chromosome]) -> Self {
+        let n = chromosomes.len();
+        let genes = chromosomes[0].len();
+
+        assert!(chromosomes
+            .iter()
+            .all(|chromosome| chromosome.len() == genes));
+
+        let mut distance = 0.0;
+
+        for (idx, a) in chromosomes.iter().enumerate() {
+            for b in &chromosomes[idx + 1..] {
+                distance += a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+            }
+        }
+
+        let pairs = n * (n - 1) / 2;
+        let gene_variance = (0..genes)
+            .map(|gene| {
+                let avg = chromosomes.iter().map(|c| c[gene]).sum::<f32>() / n as f32;
+
+                chromosomes
+                    .iter()
+                    .map(|c| (c[gene] - avg).powi(2))
+                    .sum::<f32>()
+                    / n as f32
+            })
+            .collect();
+
+        Self {
+            mean_distance: if pairs == 0 {
+                0.0
+            } else {
+                distance / pairs as f32
+            },
+            gene_variance,
+        }
+    }
+
+    /// Euclidean distance between chromosomes, averaged over every pair.
+    pub fn mean_distance(&self) -> f32 {
+        self.mean_distance
+    }
+
+    /// Variance of every gene across the population.
+    pub fn gene_variance(&self) -> &[f32] {
+        &self.gene_variance
+    }
+}
+
+/// Linear interpolation between the closest ranks of `sorted`.
+fn quantile(sorted: &[f32], q: f32) -> f32 {
+    let pos = q * (sorted.len() - 1) as f32;
+    let (lower, upper) = (pos.floor() as usize, pos.ceil() as usize);
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_fitness() {
+        let stats = Statistics::from_fitness([3.0, 8.0, 1.0, 4.0, 9.0, 5.0]);
+
+        assert_relative_eq!(stats.min_fitness(), 1.0);
+        assert_relative_eq!(stats.max_fitness(), 9.0);
+        assert_relative_eq!(stats.avg_fitness(), 5.0);
+        assert_relative_eq!(stats.median_fitness(), 4.5);
+        assert_relative_eq!(stats.stddev_fitness(), (23.0f32 / 3.0).sqrt());
+        assert_eq!(stats.quartiles_fitness(), (3.25, 7.25));
+        assert_eq!(stats.best_index(), 4);
+        assert!(stats.diversity().is_none());
+    }
+
+    #[test]
+    fn test_diversity() {
+        let population: Vec<_> = [[0.0, 0.0], [3.0, 4.0], [0.0, 4.0]]
+            .into_iter()
+            .map(|genes| TestIndividual::create(genes.into_iter().collect()))
+            .collect();
+
+        let stats = Statistics::new(&population);
+        let diversity = stats.diversity().unwrap();
+
+        assert_eq!(stats.best_index(), 1);
+        assert_relative_eq!(diversity.mean_distance(), 4.0);
+        assert_relative_eq!(
+            diversity.gene_variance(),
+            [2.0, 32.0 / 9.0].as_slice(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&Statistics::from_fitness([1.0, 2.0])).unwrap();
+        let stats: Statistics = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(stats.best_index(), 1);
+        assert_relative_eq!(stats.median_fitness(), 1.5);
+    }
 }
@@ -0,0 +1,177 @@
+use crate::*;
+use rand_distr::{Distribution, Normal};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// How `Network::random_with` samples a layer's weights and biases, given
+/// its fan-in (inputs plus recurrent context) and fan-out (neurons).
+#[derive(
+    IntoStaticStr, EnumIter, Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub enum Initializer {
+    /// Weights and biases from `-range..range`.
+    /// (range)
+    Uniform(f32),
+    /// Weights from `-range..range`, biases start at zero.
+    /// (range)
+    ZeroBiases(f32),
+    /// Xavier/Glorot: weights from `±sqrt(6 / (fan_in + fan_out))`, biases
+    /// start at zero. Suits tanh and sigmoid.
+    Xavier,
+    /// He/Kaiming: weights from a normal distribution with standard
+    /// deviation `sqrt(2 / fan_in)`, biases start at zero. Suits ReLU.
+    He,
+    /// Weights and biases from a normal distribution.
+    /// (mean, std_dev)
+    Normal(f32, f32),
+}
+
+impl Default for Initializer {
+    fn default() -> Self {
+        Self::Uniform(1.0)
+    }
+}
+
+impl Initializer {
+    /// Ranges and standard deviations must be finite and non-negative.
+    pub(crate) fn check(&self) -> Result<(), NetworkError> {
+        let valid = match *self {
+            Self::Uniform(range) | Self::ZeroBiases(range) => range.is_finite() && range >= 0.0,
+            Self::Xavier | Self::He => true,
+            Self::Normal(mean, std_dev) => {
+                mean.is_finite() && std_dev.is_finite() && std_dev >= 0.0
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidInitializer(*self))
+        }
+    }
+
+    pub(crate) fn weight(&self, rng: &mut dyn RngCore, fan_in: usize, fan_out: usize) -> f32 {
+        match *self {
+            Self::Uniform(range) | Self::ZeroBiases(range) => uniform(rng, range),
+            Self::Xavier => uniform(rng, (6.0 / (fan_in + fan_out).max(1) as f32).sqrt()),
+            Self::He => normal(rng, 0.0, (2.0 / fan_in.max(1) as f32).sqrt()),
+            Self::Normal(mean, std_dev) => normal(rng, mean, std_dev),
+        }
+    }
+
+    pub(crate) fn bias(&self, rng: &mut dyn RngCore) -> f32 {
+        match *self {
+            Self::Uniform(range) => uniform(rng, range),
+            Self::ZeroBiases(_) | Self::Xavier | Self::He => 0.0,
+            Self::Normal(mean, std_dev) => normal(rng, mean, std_dev),
+        }
+    }
+}
+
+fn uniform(rng: &mut dyn RngCore, range: f32) -> f32 {
+    if range > 0.0 {
+        rng.gen_range(-range..range)
+    } else {
+        0.0
+    }
+}
+
+fn normal(rng: &mut dyn RngCore, mean: f32, std_dev: f32) -> f32 {
+    Normal::new(mean, std_dev)
+        .expect("checked by `Initializer::check`")
+        .sample(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn sample(initializer: Initializer, fan_in: usize, fan_out: usize) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        (0..10_000)
+            .map(|_| initializer.weight(&mut rng, fan_in, fan_out))
+            .collect()
+    }
+
+    fn mean_and_std_dev(values: &[f32]) -> (f32, f32) {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+
+        (mean, var.sqrt())
+    }
+
+    #[test]
+    fn test_uniform() {
+        let actual = sample(Initializer::Uniform(0.5), 3, 3);
+
+        assert!(actual.iter().all(|w| (-0.5..0.5).contains(w)));
+    }
+
+    #[test]
+    fn test_xavier() {
+        let actual = sample(Initializer::Xavier, 40, 20);
+        let limit = 0.1f32.sqrt();
+
+        assert!(actual.iter().all(|w| (-limit..limit).contains(w)));
+        assert!(actual.iter().any(|w| w.abs() > 0.9 * limit));
+    }
+
+    #[test]
+    fn test_he() {
+        let (mean, std_dev) = mean_and_std_dev(&sample(Initializer::He, 50, 10));
+
+        assert_relative_eq!(mean, 0.0, epsilon = 0.01);
+        assert_relative_eq!(std_dev, 0.2, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_normal() {
+        let (mean, std_dev) = mean_and_std_dev(&sample(Initializer::Normal(1.0, 0.5), 3, 3));
+
+        assert_relative_eq!(mean, 1.0, epsilon = 0.02);
+        assert_relative_eq!(std_dev, 0.5, epsilon = 0.02);
+    }
+
+    #[test]
+    fn test_bias() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        assert_eq!(Initializer::ZeroBiases(1.0).bias(&mut rng), 0.0);
+        assert_eq!(Initializer::Xavier.bias(&mut rng), 0.0);
+        assert_eq!(Initializer::He.bias(&mut rng), 0.0);
+        assert_ne!(Initializer::Uniform(1.0).bias(&mut rng), 0.0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let layers = [
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ];
+
+        for initializer in [
+            Initializer::Normal(0.0, -1.0),
+            Initializer::Normal(0.0, f32::NAN),
+            Initializer::Normal(f32::INFINITY, 1.0),
+            Initializer::Uniform(f32::INFINITY),
+            Initializer::ZeroBiases(-1.0),
+        ] {
+            assert!(matches!(
+                Network::<f32>::try_random_with(&layers, &initializer, &mut rng),
+                Err(NetworkError::InvalidInitializer(_))
+            ));
+        }
+    }
+}
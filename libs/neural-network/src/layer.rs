@@ -1,49 +1,195 @@
 use crate::*;
+use strum_macros::{EnumIter, IntoStaticStr};
 
-pub(crate) struct Layer {
-    pub(crate) weights: DMatrix<f32>,
-    pub(crate) biases: DVector<f32>,
+#[derive(Clone)]
+pub(crate) struct Layer<S: Scalar = f32> {
+    pub(crate) weights: DMatrix<S>,
+    /// Which of `weights` are connected, `None` while all of them are.
+    /// Disconnected weights are held at zero, so propagation needn't check.
+    pub(crate) mask: Option<DMatrix<bool>>,
+    pub(crate) biases: DVector<S>,
+    pub(crate) activation: Activation,
+    pub(crate) kind: LayerKind,
+    /// Weights applied to `context`; `neurons x context.len()`.
+    pub(crate) recurrent: DMatrix<S>,
+    /// Activations fed back from the previous step; empty for dense layers.
+    pub(crate) context: DVector<S>,
 }
 
+#[derive(
+    IntoStaticStr,
+    EnumIter,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum LayerKind {
+    #[default]
+    Dense,
+    /// Feeds the layer's own previous activations back as extra inputs.
+    Elman,
+    /// Feeds the network's previous output back as extra inputs.
+    Jordan,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct LayerTopology {
     pub neurons: usize,
+    /// Applied to this layer's outputs; ignored for the input layer.
+    #[serde(default)]
+    pub activation: Activation,
+    /// Ignored for the input layer.
+    #[serde(default)]
+    pub kind: LayerKind,
 }
 
-impl Layer {
-    pub(crate) fn propagate(&self, input: DVector<f32>) -> DVector<f32> {
-        Self::relu(&(&self.weights * input + &self.biases))
+impl LayerTopology {
+    /// Number of recurrent inputs this layer takes in a network whose
+    /// output layer has `output_size` neurons.
+    pub(crate) fn context_size(&self, output_size: usize) -> usize {
+        match self.kind {
+            LayerKind::Dense => 0,
+            LayerKind::Elman => self.neurons,
+            LayerKind::Jordan => output_size,
+        }
     }
+}
 
-    fn relu(input: &DVector<f32>) -> DVector<f32> {
-        input.map(|x| x.max(0.0))
+impl<S: Scalar> Layer<S> {
+    /// Doesn't advance `context`; that's up to the owning `Network`.
+    pub(crate) fn propagate(&self, input: &DVector<S>) -> DVector<S> {
+        self.activation.apply_vector(&self.pre_activation(input))
     }
 
-    pub(crate) fn random(input_size: usize, output_size: usize, rng: &mut dyn RngCore) -> Self {
-        Self {
-            weights: DMatrix::from_fn(output_size, input_size, |_, _| rng.gen_range(-1.0..1.0)),
-            biases: DVector::from_fn(output_size, |_, _| rng.gen_range(-1.0..1.0)),
+    pub(crate) fn pre_activation(&self, input: &DVector<S>) -> DVector<S> {
+        &self.weights * input + &self.recurrent * &self.context + &self.biases
+    }
+
+    pub(crate) fn propagate_column(&self, input: DVectorView<S>, mut output: DVectorViewMut<S>) {
+        output.gemv(S::one(), &self.weights, &input, S::zero());
+
+        if !self.context.is_empty() {
+            output.gemv(S::one(), &self.recurrent, &self.context, S::one());
         }
+
+        output += &self.biases;
+        output.apply(|x| *x = self.activation.apply(*x));
     }
 
-    pub(crate) fn from_weights(
+    /// Stores this layer's `output` for the next step if it's an Elman
+    /// layer.
+    pub(crate) fn remember_own(&mut self, output: &[S]) {
+        if self.kind == LayerKind::Elman {
+            self.context.copy_from_slice(output);
+        }
+    }
+
+    /// Stores the network's `output` for the next step if this is a Jordan
+    /// layer.
+    pub(crate) fn remember_network(&mut self, output: &[S]) {
+        if self.kind == LayerKind::Jordan {
+            self.context.copy_from_slice(output);
+        }
+    }
+
+    pub(crate) fn random(
         input_size: usize,
-        output_size: usize,
-        weights: &mut dyn Iterator<Item = f32>,
+        output: &LayerTopology,
+        context_size: usize,
+        initializer: &Initializer,
+        rng: &mut dyn RngCore,
     ) -> Self {
-        let mut biases: Vec<f32> = Vec::with_capacity(output_size);
-        let mut weightsvec: Vec<f32> = Vec::with_capacity(output_size * input_size);
+        let output_size = output.neurons;
+        let fan_in = input_size + context_size;
 
-        for _ in 0..output_size {
-            biases.push(weights.next().unwrap());
+        Self {
+            weights: DMatrix::from_fn(output_size, input_size, |_, _| {
+                scalar(initializer.weight(rng, fan_in, output_size))
+            }),
+            mask: None,
+            biases: DVector::from_fn(output_size, |_, _| scalar(initializer.bias(rng))),
+            activation: output.activation,
+            kind: output.kind,
+            recurrent: DMatrix::from_fn(output_size, context_size, |_, _| {
+                scalar(initializer.weight(rng, fan_in, output_size))
+            }),
+            context: DVector::zeros(context_size),
         }
+    }
 
-        for _ in 0..input_size * output_size {
-            weightsvec.push(weights.next().unwrap())
-        }
+    /// Reads the biases, then the input weights, then the recurrent weights.
+    pub(crate) fn from_weights(
+        input_size: usize,
+        output: &LayerTopology,
+        context_size: usize,
+        weights: &mut dyn Iterator<Item = S>,
+    ) -> Result<Self, NetworkError> {
+        let output_size = output.neurons;
+        let input_weights = input_size.checked_mul(output_size);
+        let recurrent_weights = context_size.checked_mul(output_size);
+        let (input_weights, recurrent_weights) = input_weights
+            .zip(recurrent_weights)
+            .ok_or(NetworkError::TooLarge)?;
+        let expected = output_size
+            .checked_add(input_weights)
+            .and_then(|len| len.checked_add(recurrent_weights))
+            .ok_or(NetworkError::TooLarge)?;
 
-        Self {
+        // The sizes may come from an untrusted file, so nothing is reserved
+        // up front: every part only grows as far as `weights` goes.
+        let mut taken = 0;
+        let mut read = |len| {
+            let part: Vec<S> = (&mut *weights).take(len).collect();
+
+            taken += part.len();
+
+            if part.len() < len {
+                Err(NetworkError::TooFewWeights(expected, taken))
+            } else {
+                Ok(part)
+            }
+        };
+
+        let biases = read(output_size)?;
+        let weightsvec = read(input_weights)?;
+        let recurrentvec = read(recurrent_weights)?;
+
+        Ok(Self {
             weights: DMatrix::from_vec(output_size, input_size, weightsvec),
+            mask: None,
             biases: DVector::from_vec(biases),
+            activation: output.activation,
+            kind: output.kind,
+            recurrent: DMatrix::from_vec(output_size, context_size, recurrentvec),
+            context: DVector::zeros(context_size),
+        })
+    }
+
+    /// Whether the weight at `idx`, in column-major order, is connected.
+    pub(crate) fn is_connected(&self, idx: usize) -> bool {
+        self.mask.as_ref().is_none_or(|mask| mask[idx])
+    }
+
+    /// Zeroes the disconnected weights.
+    pub(crate) fn apply_mask(&mut self) {
+        if let Some(mask) = &self.mask {
+            for (weight, &connected) in self.weights.iter_mut().zip(mask.iter()) {
+                if !connected {
+                    *weight = S::zero();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn topology(&self) -> LayerTopology {
+        LayerTopology {
+            neurons: self.biases.len(),
+            activation: self.activation,
+            kind: self.kind,
         }
     }
 }
@@ -56,15 +202,68 @@ mod tests {
 
     #[test]
     fn test() {
-        let layer = Layer {
+        let layer: Layer = Layer {
             weights: DMatrix::from_vec(2, 3, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]),
+            mask: None,
             biases: DVector::from_vec(vec![0.0, 0.0]),
+            activation: Activation::ReLU,
+            kind: LayerKind::Dense,
+            recurrent: DMatrix::zeros(2, 0),
+            context: DVector::zeros(0),
         };
 
         let inputs = &[0.5, 0.0, -0.5];
 
-        let actual = layer.propagate(DVector::from_vec(inputs.to_vec()));
+        let actual = layer.propagate(&DVector::from_vec(inputs.to_vec()));
 
         assert_relative_eq!(actual.as_slice(), &[0.0, 0.0].as_slice());
     }
+
+    #[test]
+    fn test_activation() {
+        let layer: Layer = Layer {
+            weights: DMatrix::from_vec(2, 3, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]),
+            mask: None,
+            biases: DVector::from_vec(vec![0.0, 0.0]),
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+            recurrent: DMatrix::zeros(2, 0),
+            context: DVector::zeros(0),
+        };
+
+        let inputs = &[0.5, 0.0, -0.5];
+
+        let actual = layer.propagate(&DVector::from_vec(inputs.to_vec()));
+
+        assert_relative_eq!(actual.as_slice(), &[-0.2, -0.2].as_slice());
+    }
+
+    #[test]
+    fn test_from_weights_too_few() {
+        let mut weights = vec![0.1, 0.2, 0.3, 0.4].into_iter();
+        let output = LayerTopology {
+            neurons: 2,
+            activation: Activation::ReLU,
+            kind: LayerKind::Dense,
+        };
+        let actual = Layer::from_weights(2, &output, 0, &mut weights);
+
+        assert!(matches!(actual, Err(NetworkError::TooFewWeights(6, 4))));
+    }
+
+    #[test]
+    fn test_from_weights_recurrent() {
+        let mut weights = (1..=10).map(|x| x as f32);
+        let output = LayerTopology {
+            neurons: 2,
+            activation: Activation::ReLU,
+            kind: LayerKind::Elman,
+        };
+        let layer = Layer::from_weights(2, &output, 2, &mut weights).unwrap();
+
+        assert_relative_eq!(layer.biases.as_slice(), [1.0, 2.0].as_slice());
+        assert_relative_eq!(layer.weights.as_slice(), [3.0, 4.0, 5.0, 6.0].as_slice());
+        assert_relative_eq!(layer.recurrent.as_slice(), [7.0, 8.0, 9.0, 10.0].as_slice());
+        assert_relative_eq!(layer.context.as_slice(), [0.0, 0.0].as_slice());
+    }
 }
@@ -1,87 +1,329 @@
-#![feature(array_windows)]
-#![feature(type_alias_impl_trait)]
 #![feature(impl_trait_in_assoc_type)]
 
-use crate::layer::*;
-pub use crate::{geneticalgorithm::*, layer::LayerTopology};
-use nalgebra::{DMatrix, DVector};
+pub use crate::{
+    activation::*,
+    controller::*,
+    ctrnn::*,
+    error::*,
+    export::*,
+    format::*,
+    geneticalgorithm::*,
+    initializer::*,
+    layer::{LayerKind, LayerTopology},
+    neat::*,
+    plasticity::{Plasticity, WEIGHT_LIMIT},
+    quantize::*,
+    scalar::Scalar,
+    scratch::*,
+    sparse::*,
+    trace::*,
+    train::*,
+};
+use crate::{
+    layer::*,
+    plasticity::Plastic,
+    scalar::{scalar, total_cmp},
+};
+use nalgebra::{DMatrix, DVector, DVectorView, DVectorViewMut};
 use rand::{Rng, RngCore};
 
+mod activation;
+mod controller;
+mod ctrnn;
+mod error;
+mod export;
+mod format;
 pub mod geneticalgorithm;
+mod initializer;
 mod layer;
+pub mod neat;
+mod plasticity;
+mod quantize;
+mod scalar;
+mod scratch;
+mod sparse;
+mod trace;
+mod train;
 
-pub struct Network {
-    layers: Vec<Layer>,
+/// Generic over its `Scalar`; the scratch, batched, serialization, export
+/// and training APIs are `f32` only.
+pub struct Network<S: Scalar = f32> {
+    layers: Vec<Layer<S>>,
+    plastic: Option<Plastic<S>>,
 }
 
-impl Network {
-    pub fn propagate(&self, input: DVector<f32>) -> DVector<f32> {
-        self.layers
-            .iter()
-            .fold(input, |input, layer| layer.propagate(input))
+impl<S: Scalar> Network<S> {
+    /// Advances the state of any recurrent layers, and learns if the
+    /// network is plastic.
+    pub fn propagate(&mut self, input: DVector<S>) -> DVector<S> {
+        let plastic = Plastic::begin(&mut self.plastic);
+
+        let output = self
+            .layers
+            .iter_mut()
+            .enumerate()
+            .fold(input, |input, (idx, layer)| {
+                let output = layer.propagate(&input);
+
+                if let Some((plastic, modulation)) = plastic {
+                    plastic.learn(idx, layer, input.as_slice(), output.as_slice(), modulation);
+                }
+
+                layer.remember_own(output.as_slice());
+
+                output
+            });
+
+        self.remember_output(output.as_slice());
+
+        output
+    }
+
+    /// Forgets everything the recurrent layers have seen so far, and
+    /// everything a plastic network has learned.
+    pub fn reset_state(&mut self) {
+        for layer in &mut self.layers {
+            layer.context.fill(S::zero());
+        }
+
+        if let Some(plastic) = &self.plastic {
+            for (layer, born) in self.layers.iter_mut().zip(&plastic.born) {
+                layer.weights.copy_from(born);
+            }
+        }
+    }
+
+    pub(crate) fn remember_output(&mut self, output: &[S]) {
+        for layer in &mut self.layers {
+            layer.remember_network(output);
+        }
     }
 
+    /// Samples with `Initializer::default()`.
     pub fn random(layers: &[LayerTopology], rng: &mut dyn RngCore) -> Self {
-        assert!(layers.len() > 1);
+        Self::try_random(layers, rng).unwrap()
+    }
+
+    pub fn try_random(
+        layers: &[LayerTopology],
+        rng: &mut dyn RngCore,
+    ) -> Result<Self, NetworkError> {
+        Self::try_random_with(layers, &Initializer::default(), rng)
+    }
+
+    pub fn random_with(
+        layers: &[LayerTopology],
+        initializer: &Initializer,
+        rng: &mut dyn RngCore,
+    ) -> Self {
+        Self::try_random_with(layers, initializer, rng).unwrap()
+    }
+
+    pub fn try_random_with(
+        layers: &[LayerTopology],
+        initializer: &Initializer,
+        rng: &mut dyn RngCore,
+    ) -> Result<Self, NetworkError> {
+        if layers.len() < 2 {
+            return Err(NetworkError::TooFewLayers(layers.len()));
+        }
+
+        initializer.check()?;
 
-        Self {
+        let output_size = layers[layers.len() - 1].neurons;
+
+        Ok(Self {
             layers: layers
                 .array_windows::<2>()
-                .map(|[fst, snd]| Layer::random(fst.neurons, snd.neurons, rng))
+                .map(|[fst, snd]| {
+                    Layer::random(
+                        fst.neurons,
+                        snd,
+                        snd.context_size(output_size),
+                        initializer,
+                        rng,
+                    )
+                })
                 .collect(),
-        }
+            plastic: None,
+        })
     }
 
-    pub fn weights(&self) -> impl Iterator<Item = f32> + '_ {
+    /// A plastic network's weights are the ones it was born with, followed
+    /// by its learning coefficients; what it learns isn't inherited.
+    pub fn weights(&self) -> impl Iterator<Item = S> + '_ {
         self.layers
             .iter()
-            .flat_map(|layer| layer.biases.iter().chain(layer.weights.iter()))
+            .enumerate()
+            .flat_map(|(idx, layer)| {
+                let weights = match &self.plastic {
+                    Some(plastic) => plastic.born[idx].iter(),
+                    None => layer.weights.iter(),
+                };
+
+                layer
+                    .biases
+                    .iter()
+                    .chain(weights)
+                    .chain(layer.recurrent.iter())
+            })
+            .chain(
+                self.plastic
+                    .iter()
+                    .flat_map(|plastic| plastic.coefficients.iter().flatten()),
+            )
             .copied()
     }
 
-    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Self {
-        assert!(layers.len() > 1);
+    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = S>) -> Self {
+        Self::try_from_weights(layers, weights).unwrap()
+    }
+
+    pub fn try_from_weights(
+        layers: &[LayerTopology],
+        weights: impl IntoIterator<Item = S>,
+    ) -> Result<Self, NetworkError> {
+        Self::try_from_plastic_weights(layers, None, weights)
+    }
+
+    pub fn from_plastic_weights(
+        layers: &[LayerTopology],
+        plasticity: Option<Plasticity>,
+        weights: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self::try_from_plastic_weights(layers, plasticity, weights).unwrap()
+    }
+
+    /// Like `try_from_weights`, but with `plasticity`'s coefficients after
+    /// the weights, as laid out by `weights`.
+    pub fn try_from_plastic_weights(
+        layers: &[LayerTopology],
+        plasticity: Option<Plasticity>,
+        weights: impl IntoIterator<Item = S>,
+    ) -> Result<Self, NetworkError> {
+        if layers.len() < 2 {
+            return Err(NetworkError::TooFewLayers(layers.len()));
+        }
+
+        let output_size = layers[layers.len() - 1].neurons;
+        let genes = plasticity.map_or(0, |rule| rule.genes());
+        let expected = layers
+            .array_windows::<2>()
+            .try_fold(0usize, |sum, [fst, snd]| {
+                fst.neurons
+                    .checked_mul(1 + genes)?
+                    .checked_add(snd.context_size(output_size))?
+                    .checked_add(1)?
+                    .checked_mul(snd.neurons)?
+                    .checked_add(sum)
+            })
+            .ok_or(NetworkError::TooLarge)?;
 
         let mut weights = weights.into_iter();
+        let mut taken = 0;
 
         let layers = layers
             .array_windows::<2>()
-            .map(|[fst, snd]| Layer::from_weights(fst.neurons, snd.neurons, &mut weights))
-            .collect();
+            .map(|[fst, snd]| {
+                let context_size = snd.context_size(output_size);
+                let layer = Layer::from_weights(fst.neurons, snd, context_size, &mut weights)
+                    .map_err(|err| match err {
+                        NetworkError::TooFewWeights(_, actual) => {
+                            NetworkError::TooFewWeights(expected, taken + actual)
+                        }
+                        err => err,
+                    })?;
+
+                taken += layer.biases.len() + layer.weights.len() + layer.recurrent.len();
+
+                Ok(layer)
+            })
+            .collect::<Result<Vec<_>, NetworkError>>()?;
+
+        let plastic = match plasticity {
+            Some(rule) => {
+                let coefficients = layers
+                    .iter()
+                    .map(|layer| {
+                        let len = layer.weights.len() * rule.genes();
+                        let coefficients: Vec<_> = weights.by_ref().take(len).collect();
+
+                        taken += coefficients.len();
 
-        assert!(weights.next().is_none());
+                        if coefficients.len() < len {
+                            Err(NetworkError::TooFewWeights(expected, taken))
+                        } else {
+                            Ok(coefficients)
+                        }
+                    })
+                    .collect::<Result<_, NetworkError>>()?;
+                let born = layers.iter().map(|layer| layer.weights.clone()).collect();
 
-        Self { layers }
+                Some(Plastic::new(rule, coefficients, born))
+            }
+            None => None,
+        };
+
+        if weights.next().is_some() {
+            return Err(NetworkError::LeftoverWeights(expected));
+        }
+
+        Ok(Self { layers, plastic })
+    }
+
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        let input = LayerTopology {
+            neurons: self.layers[0].weights.ncols(),
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+        };
+
+        std::iter::once(input)
+            .chain(self.layers.iter().map(Layer::topology))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use approx::assert_relative_eq;
+    use approx::{assert_relative_eq, assert_relative_ne};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
     mod propagate {
         use super::*;
 
         #[test]
         fn test() {
-            let network = Network {
+            let mut network: Network = Network {
                 layers: vec![
                     Layer {
                         weights: DMatrix::from_vec(2, 3, vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]),
+                        mask: None,
                         biases: DVector::from_vec(vec![0.0, 0.0]),
+                        activation: Activation::ReLU,
+                        kind: LayerKind::Dense,
+                        recurrent: DMatrix::zeros(2, 0),
+                        context: DVector::zeros(0),
                     },
                     Layer {
                         weights: DMatrix::from_vec(1, 2, vec![0.3, -0.3]),
+                        mask: None,
                         biases: DVector::from_vec(vec![0.0]),
+                        activation: Activation::Tanh,
+                        kind: LayerKind::Dense,
+                        recurrent: DMatrix::zeros(1, 0),
+                        context: DVector::zeros(0),
                     },
                 ],
+                plastic: None,
             };
 
             let inputs = DVector::from_vec(vec![0.1, 0.2, 0.3]);
 
             let actual = network.propagate(inputs.clone());
-            let expected = network.layers[1].propagate(network.layers[0].propagate(inputs));
+            let expected = network.layers[1].propagate(&network.layers[0].propagate(&inputs));
 
             assert_relative_eq!(actual.as_slice(), expected.as_slice());
         }
@@ -92,17 +334,28 @@ mod test {
 
         #[test]
         fn test() {
-            let network = Network {
+            let network: Network = Network {
                 layers: vec![
                     Layer {
                         weights: DMatrix::from_vec(2, 2, vec![0.2, 0.3, 0.4, 0.5]),
+                        mask: None,
                         biases: DVector::from_vec(vec![0.1]),
+                        activation: Activation::ReLU,
+                        kind: LayerKind::Dense,
+                        recurrent: DMatrix::zeros(1, 0),
+                        context: DVector::zeros(0),
                     },
                     Layer {
                         weights: DMatrix::from_vec(2, 2, vec![0.7, 0.8, 0.9, 1.0]),
+                        mask: None,
                         biases: DVector::from_vec(vec![0.6]),
+                        activation: Activation::ReLU,
+                        kind: LayerKind::Dense,
+                        recurrent: DMatrix::zeros(1, 0),
+                        context: DVector::zeros(0),
                     },
                 ],
+                plastic: None,
             };
 
             let actual: Vec<f32> = network.weights().collect();
@@ -113,14 +366,304 @@ mod test {
 
         #[test]
         fn test_from_weights() {
-            let layers = &[LayerTopology { neurons: 3 }, LayerTopology { neurons: 2 }];
+            let layers = &[
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+            ];
 
             let weights = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
 
-            let network = Network::from_weights(layers, weights.clone());
+            let network: Network = Network::from_weights(layers, weights.clone());
             let actual: Vec<_> = network.weights().collect();
 
             assert_relative_eq!(actual.as_slice(), weights.as_slice(),);
         }
+
+        #[test]
+        fn test_from_weights_activation() {
+            let layers = &[
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 1,
+                    activation: Activation::Tanh,
+                    kind: LayerKind::Dense,
+                },
+            ];
+
+            let mut network: Network = Network::from_weights(layers, vec![0.0, -1.0, -1.0]);
+            let actual = network.propagate(DVector::from_vec(vec![0.5, 0.5]));
+
+            assert_eq!(network.layers[0].activation, Activation::Tanh);
+            assert_relative_eq!(actual.as_slice(), [-0.7615942].as_slice());
+        }
+
+        #[test]
+        fn test_try_from_weights_too_few_layers() {
+            let layers = &[LayerTopology {
+                neurons: 3,
+                activation: Activation::ReLU,
+                kind: LayerKind::Dense,
+            }];
+
+            let actual = Network::<f32>::try_from_weights(layers, vec![]);
+
+            assert!(matches!(actual, Err(NetworkError::TooFewLayers(1))));
+        }
+
+        #[test]
+        fn test_try_from_weights_too_few_weights() {
+            let layers = &[
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 1,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+            ];
+
+            let actual = Network::<f32>::try_from_weights(layers, vec![0.1; 9]);
+
+            assert!(matches!(actual, Err(NetworkError::TooFewWeights(11, 9))));
+        }
+
+        #[test]
+        fn test_try_from_weights_leftover_weights() {
+            let layers = &[
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+            ];
+
+            let actual = Network::<f32>::try_from_weights(layers, vec![0.1; 9]);
+
+            assert!(matches!(actual, Err(NetworkError::LeftoverWeights(8))));
+        }
+
+        #[test]
+        fn test_try_from_weights_huge_layers() {
+            let layer = |neurons| LayerTopology {
+                neurons,
+                activation: Activation::ReLU,
+                kind: LayerKind::Elman,
+            };
+
+            // Fits a `usize`, but there's nowhere near enough weights.
+            let actual =
+                Network::<f32>::try_from_weights(&[layer(1 << 20), layer(1 << 20)], [0.1; 9]);
+
+            assert!(matches!(actual, Err(NetworkError::TooFewWeights(_, 9))));
+
+            let actual =
+                Network::<f32>::try_from_weights(&[layer(usize::MAX / 2), layer(3)], [0.1; 9]);
+
+            assert!(matches!(actual, Err(NetworkError::TooLarge)));
+        }
+    }
+
+    mod topology {
+        use super::*;
+
+        #[test]
+        fn test() {
+            let layers = [
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::ReLU,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 1,
+                    activation: Activation::Sigmoid,
+                    kind: LayerKind::Dense,
+                },
+            ];
+
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network: Network = Network::random(&layers, &mut rng);
+
+            assert_eq!(network.topology(), layers);
+        }
+    }
+
+    mod recurrent {
+        use super::*;
+        use test_case::test_case;
+
+        fn layers(kind: LayerKind) -> [LayerTopology; 3] {
+            [
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::Tanh,
+                    kind,
+                },
+                LayerTopology {
+                    neurons: 1,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+            ]
+        }
+
+        #[test_case(LayerKind::Elman, 22)]
+        #[test_case(LayerKind::Jordan, 16)]
+        fn test_weights_roundtrip(kind: LayerKind, expected_len: usize) {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network: Network = Network::random(&layers(kind), &mut rng);
+            let weights: Vec<_> = network.weights().collect();
+
+            let actual = Network::from_weights(&layers(kind), weights.clone());
+            let actual: Vec<_> = actual.weights().collect();
+
+            assert_eq!(weights.len(), expected_len);
+            assert_relative_eq!(actual.as_slice(), weights.as_slice());
+        }
+
+        #[test_case(LayerKind::Elman)]
+        #[test_case(LayerKind::Jordan)]
+        fn test_state(kind: LayerKind) {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network = Network::random(&layers(kind), &mut rng);
+            let input = DVector::from_vec(vec![0.5, -0.5]);
+
+            let first = network.propagate(input.clone());
+            let second = network.propagate(input.clone());
+
+            network.reset_state();
+
+            let after_reset = network.propagate(input);
+
+            assert_relative_ne!(first.as_slice(), second.as_slice());
+            assert_relative_eq!(first.as_slice(), after_reset.as_slice());
+        }
+
+        #[test]
+        fn test_dense_is_stateless() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network = Network::random(&layers(LayerKind::Dense), &mut rng);
+            let input = DVector::from_vec(vec![0.5, -0.5]);
+
+            let first = network.propagate(input.clone());
+            let second = network.propagate(input);
+
+            assert_relative_eq!(first.as_slice(), second.as_slice());
+        }
+    }
+
+    mod random {
+        use super::*;
+
+        #[test]
+        fn test_try_random_too_few_layers() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let actual = Network::<f32>::try_random(&[], &mut rng);
+
+            assert!(matches!(actual, Err(NetworkError::TooFewLayers(0))));
+        }
+
+        #[test]
+        fn test_random_with() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let layers = [
+                LayerTopology {
+                    neurons: 30,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 10,
+                    activation: Activation::Tanh,
+                    kind: LayerKind::Dense,
+                },
+            ];
+
+            let network: Network = Network::random_with(&layers, &Initializer::Xavier, &mut rng);
+            let limit = (6.0f32 / 40.0).sqrt();
+
+            assert!(network.layers[0].biases.iter().all(|&b| b == 0.0));
+            assert!(network.layers[0].weights.iter().all(|w| w.abs() < limit));
+        }
+    }
+
+    mod scalar {
+        use super::*;
+
+        fn layers() -> [LayerTopology; 3] {
+            [
+                LayerTopology {
+                    neurons: 3,
+                    activation: Activation::Identity,
+                    kind: LayerKind::Dense,
+                },
+                LayerTopology {
+                    neurons: 4,
+                    activation: Activation::LeakyReLU(0.1),
+                    kind: LayerKind::Elman,
+                },
+                LayerTopology {
+                    neurons: 2,
+                    activation: Activation::Sigmoid,
+                    kind: LayerKind::Dense,
+                },
+            ]
+        }
+
+        #[test]
+        fn test_f64_matches_f32() {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network: Network = Network::random(&layers(), &mut rng);
+            let mut network_f64: Network<f64> =
+                Network::from_weights(&layers(), network.weights().map(f64::from));
+            let input = [0.3, -0.7, 1.1];
+
+            for _ in 0..3 {
+                let expected = network.propagate(DVector::from_vec(input.to_vec()));
+                let actual =
+                    network_f64.propagate(DVector::from_vec(input.map(f64::from).to_vec()));
+
+                assert_relative_eq!(
+                    actual.as_slice(),
+                    expected.map(f64::from).as_slice(),
+                    epsilon = 1e-6
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,470 @@
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NodeKind {
+    Input,
+    Hidden,
+    Output,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+    /// Unused for input nodes.
+    pub bias: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ConnectionGene {
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+/// NEAT genome. Nodes are kept sorted by id and connections by innovation
+/// number; connections never form a cycle.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Genome {
+    nodes: Vec<NodeGene>,
+    connections: Vec<ConnectionGene>,
+}
+
+impl Genome {
+    /// Every input connected straight to every output, with no hidden
+    /// nodes. Node ids and innovations match `Innovations::new`.
+    pub fn minimal(inputs: usize, outputs: usize, rng: &mut dyn RngCore) -> Self {
+        let nodes = (0..inputs)
+            .map(|id| NodeGene {
+                id,
+                kind: NodeKind::Input,
+                bias: 0.0,
+            })
+            .chain((inputs..inputs + outputs).map(|id| NodeGene {
+                id,
+                kind: NodeKind::Output,
+                bias: rng.gen_range(-1.0..1.0),
+            }))
+            .collect();
+
+        let mut connections = Vec::with_capacity(inputs * outputs);
+
+        for input in 0..inputs {
+            for output in 0..outputs {
+                connections.push(ConnectionGene {
+                    innovation: Innovations::minimal_innovation(outputs, input, output),
+                    from: input,
+                    to: inputs + output,
+                    weight: rng.gen_range(-1.0..1.0),
+                    enabled: true,
+                });
+            }
+        }
+
+        Self { nodes, connections }
+    }
+
+    pub fn nodes(&self) -> &[NodeGene] {
+        &self.nodes
+    }
+
+    pub fn connections(&self) -> &[ConnectionGene] {
+        &self.connections
+    }
+
+    pub fn node(&self, id: usize) -> Option<&NodeGene> {
+        self.nodes
+            .binary_search_by_key(&id, |node| node.id)
+            .ok()
+            .map(|idx| &self.nodes[idx])
+    }
+
+    /// Mutates every bias and connection weight as if they were one
+    /// `Chromosome`. Genomes don't keep strategy parameters, so
+    /// `Mutation::SelfAdaptive` always starts from its initial step sizes.
+    pub fn mutate_weights(&mut self, rng: &mut dyn RngCore, mutation: &Mutation) {
+        let mut genes: Chromosome = self
+            .nodes
+            .iter()
+            .filter(|node| node.kind != NodeKind::Input)
+            .map(|node| node.bias)
+            .chain(self.connections.iter().map(|conn| conn.weight))
+            .collect();
+
+        mutation.mutate(rng, &mut genes);
+
+        let mut genes = genes.into_iter();
+
+        for node in &mut self.nodes {
+            if node.kind != NodeKind::Input {
+                node.bias = genes.next().unwrap();
+            }
+        }
+
+        for conn in &mut self.connections {
+            conn.weight = genes.next().unwrap();
+        }
+    }
+
+    /// Connects two previously unconnected nodes, as long as that doesn't
+    /// close a cycle. Returns whether a connection was added.
+    pub fn mutate_add_connection(
+        &mut self,
+        rng: &mut dyn RngCore,
+        innovations: &mut Innovations,
+    ) -> bool {
+        const ATTEMPTS: usize = 20;
+
+        for _ in 0..ATTEMPTS {
+            let from = self.nodes[rng.gen_range(0..self.nodes.len())];
+            let to = self.nodes[rng.gen_range(0..self.nodes.len())];
+
+            if to.kind == NodeKind::Input
+                || from.id == to.id
+                || self.connected(from.id, to.id)
+                || self.reaches(to.id, from.id)
+            {
+                continue;
+            }
+
+            self.insert_connection(ConnectionGene {
+                innovation: innovations.connection(from.id, to.id),
+                from: from.id,
+                to: to.id,
+                weight: rng.gen_range(-1.0..1.0),
+                enabled: true,
+            });
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Splits a random enabled connection `a -> b` into `a -> new -> b`.
+    /// The incoming connection gets weight 1 and the outgoing one keeps the
+    /// old weight, so the behaviour barely changes. Returns whether a node
+    /// was added.
+    pub fn mutate_add_node(
+        &mut self,
+        rng: &mut dyn RngCore,
+        innovations: &mut Innovations,
+    ) -> bool {
+        let enabled: Vec<_> = (0..self.connections.len())
+            .filter(|&idx| self.connections[idx].enabled)
+            .collect();
+
+        let Some(&idx) = enabled.get(rng.gen_range(0..enabled.len().max(1))) else {
+            return false;
+        };
+
+        let conn = self.connections[idx];
+        let id = innovations.split(conn.innovation);
+
+        // The same connection was split before and re-enabled by crossover.
+        if self.node(id).is_some() {
+            return false;
+        }
+
+        self.connections[idx].enabled = false;
+
+        let pos = self.nodes.partition_point(|node| node.id < id);
+
+        self.nodes.insert(
+            pos,
+            NodeGene {
+                id,
+                kind: NodeKind::Hidden,
+                bias: 0.0,
+            },
+        );
+
+        self.insert_connection(ConnectionGene {
+            innovation: innovations.connection(conn.from, id),
+            from: conn.from,
+            to: id,
+            weight: 1.0,
+            enabled: true,
+        });
+
+        self.insert_connection(ConnectionGene {
+            innovation: innovations.connection(id, conn.to),
+            from: id,
+            to: conn.to,
+            weight: conn.weight,
+            enabled: true,
+        });
+
+        true
+    }
+
+    /// Aligns the parents on innovation numbers. Matching genes are picked
+    /// from either parent at random; disjoint and excess genes come from
+    /// `fitter`, so the child has exactly its structure. A gene disabled in
+    /// either parent stays disabled with `disable_chance`.
+    pub fn crossover(
+        rng: &mut dyn RngCore,
+        fitter: &Genome,
+        other: &Genome,
+        disable_chance: f32,
+    ) -> Genome {
+        let nodes = fitter
+            .nodes
+            .iter()
+            .map(|node| match other.node(node.id) {
+                Some(matching) if rng.gen_bool(0.5) => *matching,
+                _ => *node,
+            })
+            .collect();
+
+        let connections = fitter
+            .connections
+            .iter()
+            .map(|conn| match other.connection(conn.innovation) {
+                Some(matching) => {
+                    let mut child = if rng.gen_bool(0.5) { *conn } else { *matching };
+
+                    child.enabled = if !conn.enabled || !matching.enabled {
+                        !rng.gen_bool(disable_chance as _)
+                    } else {
+                        true
+                    };
+
+                    child
+                }
+                None => *conn,
+            })
+            .collect();
+
+        Genome { nodes, connections }
+    }
+
+    /// NEAT compatibility distance:
+    /// `excess_coeff * E / N + disjoint_coeff * D / N + weight_coeff * W`,
+    /// where `N` is the size of the larger genome and `W` is the mean weight
+    /// difference of the matching genes.
+    pub fn distance(&self, other: &Genome, config: &NeatConfig) -> f32 {
+        let (mut i, mut j) = (0, 0);
+        let (mut disjoint, mut matching, mut weight_diff) = (0, 0, 0.0);
+        let (a, b) = (&self.connections, &other.connections);
+
+        while i < a.len() && j < b.len() {
+            match a[i].innovation.cmp(&b[j].innovation) {
+                std::cmp::Ordering::Equal => {
+                    matching += 1;
+                    weight_diff += (a[i].weight - b[j].weight).abs();
+                    i += 1;
+                    j += 1;
+                }
+                std::cmp::Ordering::Less => {
+                    disjoint += 1;
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => {
+                    disjoint += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        let excess = (a.len() - i) + (b.len() - j);
+        let n = a.len().max(b.len()).max(1) as f32;
+        let weight_diff = if matching > 0 {
+            weight_diff / matching as f32
+        } else {
+            0.0
+        };
+
+        config.excess_coeff * excess as f32 / n
+            + config.disjoint_coeff * disjoint as f32 / n
+            + config.weight_coeff * weight_diff
+    }
+
+    fn connection(&self, innovation: usize) -> Option<&ConnectionGene> {
+        self.connections
+            .binary_search_by_key(&innovation, |conn| conn.innovation)
+            .ok()
+            .map(|idx| &self.connections[idx])
+    }
+
+    fn connected(&self, from: usize, to: usize) -> bool {
+        self.connections
+            .iter()
+            .any(|conn| conn.from == from && conn.to == to)
+    }
+
+    /// Whether `to` can be reached from `from`, following disabled
+    /// connections too since crossover may re-enable them.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut seen = vec![from];
+
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+
+            for conn in self.connections.iter().filter(|conn| conn.from == node) {
+                if !seen.contains(&conn.to) {
+                    seen.push(conn.to);
+                    stack.push(conn.to);
+                }
+            }
+        }
+
+        false
+    }
+
+    fn insert_connection(&mut self, conn: ConnectionGene) {
+        let pos = self
+            .connections
+            .partition_point(|other| other.innovation < conn.innovation);
+
+        self.connections.insert(pos, conn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn genome() -> (Genome, Innovations) {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        (Genome::minimal(3, 2, &mut rng), Innovations::new(3, 2))
+    }
+
+    #[test]
+    fn test_minimal() {
+        let (genome, _) = genome();
+
+        assert_eq!(genome.nodes().len(), 5);
+        assert_eq!(genome.connections().len(), 6);
+        assert!(genome
+            .connections()
+            .iter()
+            .enumerate()
+            .all(|(idx, conn)| conn.innovation == idx && conn.enabled));
+    }
+
+    #[test]
+    fn test_add_node() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (mut genome, mut innovations) = genome();
+
+        assert!(genome.mutate_add_node(&mut rng, &mut innovations));
+
+        let disabled: Vec<_> = genome
+            .connections()
+            .iter()
+            .filter(|conn| !conn.enabled)
+            .collect();
+
+        assert_eq!(genome.nodes().len(), 6);
+        assert_eq!(genome.nodes()[5].kind, NodeKind::Hidden);
+        assert_eq!(genome.connections().len(), 8);
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(genome.connections()[6].from, disabled[0].from);
+        assert_eq!(genome.connections()[6].to, 5);
+        assert_eq!(genome.connections()[7].from, 5);
+        assert_eq!(genome.connections()[7].to, disabled[0].to);
+        assert_eq!(genome.connections()[7].weight, disabled[0].weight);
+    }
+
+    #[test]
+    fn test_add_connection_stays_acyclic() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (mut genome, mut innovations) = genome();
+
+        for _ in 0..10 {
+            genome.mutate_add_node(&mut rng, &mut innovations);
+            genome.mutate_add_connection(&mut rng, &mut innovations);
+        }
+
+        for conn in genome.connections() {
+            assert!(!genome.reaches(conn.to, conn.from));
+            assert_ne!(genome.node(conn.to).unwrap().kind, NodeKind::Input);
+        }
+
+        assert!(genome
+            .connections()
+            .windows(2)
+            .all(|pair| pair[0].innovation < pair[1].innovation));
+    }
+
+    #[test]
+    fn test_same_mutation_same_innovation() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (mut a, mut innovations) = genome();
+        let mut b = a.clone();
+
+        a.connections.truncate(1);
+        b.connections.truncate(1);
+        a.mutate_add_node(&mut rng, &mut innovations);
+        b.mutate_add_node(&mut rng, &mut innovations);
+
+        assert_eq!(a.nodes(), b.nodes());
+        assert_eq!(
+            a.connections()
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>(),
+            b.connections()
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_crossover() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (fitter, mut innovations) = genome();
+        let mut other = fitter.clone();
+
+        other.mutate_add_node(&mut rng, &mut innovations);
+
+        let child = Genome::crossover(&mut rng, &fitter, &other, 0.75);
+
+        assert_eq!(child.nodes().len(), fitter.nodes().len());
+        assert_eq!(
+            child
+                .connections()
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>(),
+            fitter
+                .connections()
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let config = NeatConfig {
+            excess_coeff: 1.0,
+            disjoint_coeff: 2.0,
+            weight_coeff: 0.5,
+            ..Default::default()
+        };
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let (a, mut innovations) = genome();
+        let mut b = a.clone();
+
+        assert_eq!(a.distance(&b, &config), 0.0);
+
+        b.connections[0].weight += 1.0;
+        b.connections.remove(2);
+        b.mutate_add_node(&mut rng, &mut innovations);
+
+        // 5 matching genes with a total weight difference of 1, 1 disjoint
+        // gene, 2 excess genes and 7 genes in the larger genome.
+        approx::assert_relative_eq!(a.distance(&b, &config), 2.0 / 7.0 + 2.0 / 7.0 + 0.5 * 0.2);
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+/// Hands out node ids and innovation numbers, so that the same structural
+/// mutation gets the same numbers in every genome that makes it.
+#[derive(Clone, Debug)]
+pub struct Innovations {
+    next_node: usize,
+    next_innovation: usize,
+    connections: HashMap<(usize, usize), usize>,
+    splits: HashMap<usize, usize>,
+}
+
+impl Innovations {
+    /// Starts after the ids and innovations used by `Genome::minimal`.
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        let connections = (0..inputs)
+            .flat_map(|input| (0..outputs).map(move |output| (input, output)))
+            .map(|(input, output)| {
+                (
+                    (input, inputs + output),
+                    Self::minimal_innovation(outputs, input, output),
+                )
+            })
+            .collect();
+
+        Self {
+            next_node: inputs + outputs,
+            next_innovation: inputs * outputs,
+            connections,
+            splits: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn minimal_innovation(outputs: usize, input: usize, output: usize) -> usize {
+        input * outputs + output
+    }
+
+    /// Innovation number of a connection `from -> to`.
+    pub(crate) fn connection(&mut self, from: usize, to: usize) -> usize {
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            self.next_innovation += 1;
+            self.next_innovation - 1
+        })
+    }
+
+    /// Id of the node that splits the connection with `innovation`.
+    pub(crate) fn split(&mut self, innovation: usize) -> usize {
+        *self.splits.entry(innovation).or_insert_with(|| {
+            self.next_node += 1;
+            self.next_node - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection() {
+        let mut innovations = Innovations::new(2, 1);
+
+        assert_eq!(innovations.connection(0, 2), 0);
+        assert_eq!(innovations.connection(1, 2), 1);
+        assert_eq!(innovations.connection(2, 1), 2);
+        assert_eq!(innovations.connection(3, 2), 3);
+        assert_eq!(innovations.connection(2, 1), 2);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut innovations = Innovations::new(2, 1);
+
+        assert_eq!(innovations.split(0), 3);
+        assert_eq!(innovations.split(1), 4);
+        assert_eq!(innovations.split(0), 3);
+    }
+}
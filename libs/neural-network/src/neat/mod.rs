@@ -0,0 +1,324 @@
+//! NeuroEvolution of Augmenting Topologies: genomes start minimal and grow
+//! nodes and connections, while speciation protects new structure until its
+//! weights are tuned.
+
+pub use self::{genome::*, innovations::*, network::*, species::*};
+
+mod genome;
+mod innovations;
+mod network;
+mod species;
+
+use crate::*;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NeatConfig {
+    /// Applied to the biases and weights of every offspring.
+    pub mutation: Mutation,
+    pub add_node_chance: f32,
+    pub add_connection_chance: f32,
+    /// Chance that a gene disabled in either parent stays disabled.
+    pub disable_chance: f32,
+    pub excess_coeff: f32,
+    pub disjoint_coeff: f32,
+    pub weight_coeff: f32,
+    pub compatibility_threshold: f32,
+    /// Fraction of each species allowed to reproduce.
+    pub survival_threshold: f32,
+    /// Species at least this large keep their champion unchanged.
+    pub champion_min_size: usize,
+}
+
+impl Default for NeatConfig {
+    fn default() -> Self {
+        Self {
+            mutation: Mutation::UniformPerturbation(0.8, 0.5),
+            add_node_chance: 0.03,
+            add_connection_chance: 0.05,
+            disable_chance: 0.75,
+            excess_coeff: 1.0,
+            disjoint_coeff: 1.0,
+            weight_coeff: 0.4,
+            compatibility_threshold: 3.0,
+            survival_threshold: 0.2,
+            champion_min_size: 5,
+        }
+    }
+}
+
+pub trait NeatIndividual {
+    fn fitness(&self) -> f32;
+    fn genome(&self) -> &Genome;
+    fn create(genome: Genome) -> Self;
+}
+
+pub struct Neat {
+    config: NeatConfig,
+    innovations: Innovations,
+    species: Vec<Species>,
+    next_species_id: usize,
+}
+
+impl Neat {
+    pub fn new(config: NeatConfig, inputs: usize, outputs: usize) -> Self {
+        Self {
+            config,
+            innovations: Innovations::new(inputs, outputs),
+            species: Vec::new(),
+            next_species_id: 0,
+        }
+    }
+
+    pub fn config(&self) -> &NeatConfig {
+        &self.config
+    }
+
+    /// Species of the population passed to the last `evolve`.
+    pub fn species(&self) -> &[Species] {
+        &self.species
+    }
+
+    /// Returns the next generation, and `Statistics` of `population`.
+    pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, population: &[I]) -> (Vec<I>, Statistics)
+    where
+        I: NeatIndividual,
+    {
+        assert!(!population.is_empty());
+
+        self.speciate(population);
+
+        // Fitness sharing needs non-negative fitness.
+        let min_fitness = population
+            .iter()
+            .map(|individual| individual.fitness())
+            .fold(f32::INFINITY, f32::min);
+        let offset = (-min_fitness).max(0.0);
+        let fitness = |idx: usize| population[idx].fitness() + offset;
+
+        for species in &mut self.species {
+            species
+                .members
+                .sort_by(|&a, &b| fitness(b).total_cmp(&fitness(a)));
+        }
+
+        let shared: Vec<f32> = self
+            .species
+            .iter()
+            .map(|species| {
+                species.members.iter().map(|&idx| fitness(idx)).sum::<f32>() / species.len() as f32
+            })
+            .collect();
+
+        let offspring = Self::allocate(&shared, population.len());
+        let mut new_pop = Vec::with_capacity(population.len());
+
+        for (species, offspring) in self.species.iter().zip(offspring) {
+            if offspring == 0 {
+                continue;
+            }
+
+            let mut offspring = offspring;
+
+            if species.len() >= self.config.champion_min_size {
+                new_pop.push(I::create(population[species.members[0]].genome().clone()));
+                offspring -= 1;
+            }
+
+            let survivors = ((species.len() as f32 * self.config.survival_threshold).ceil()
+                as usize)
+                .clamp(1, species.len());
+            let parents = &species.members[..survivors];
+
+            for _ in 0..offspring {
+                let a = parents[rng.gen_range(0..parents.len())];
+                let b = parents[rng.gen_range(0..parents.len())];
+                let (fitter, other) = if fitness(a) >= fitness(b) {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+
+                let mut child = Genome::crossover(
+                    rng,
+                    population[fitter].genome(),
+                    population[other].genome(),
+                    self.config.disable_chance,
+                );
+
+                child.mutate_weights(rng, &self.config.mutation);
+
+                if rng.gen_bool(self.config.add_node_chance as _) {
+                    child.mutate_add_node(rng, &mut self.innovations);
+                }
+
+                if rng.gen_bool(self.config.add_connection_chance as _) {
+                    child.mutate_add_connection(rng, &mut self.innovations);
+                }
+
+                new_pop.push(I::create(child));
+            }
+        }
+
+        // Next generation is compared against a random member of this one.
+        for species in &mut self.species {
+            let member = species.members[rng.gen_range(0..species.len())];
+
+            species.set_representative(population[member].genome().clone());
+        }
+
+        let statistics =
+            Statistics::from_fitness(population.iter().map(|individual| individual.fitness()));
+
+        (new_pop, statistics)
+    }
+
+    /// Assigns every individual to the first species whose representative
+    /// is close enough, founding new species as needed. Species left empty
+    /// go extinct.
+    fn speciate<I>(&mut self, population: &[I])
+    where
+        I: NeatIndividual,
+    {
+        for species in &mut self.species {
+            species.members.clear();
+        }
+
+        for (idx, individual) in population.iter().enumerate() {
+            let genome = individual.genome();
+            let found = self.species.iter_mut().find(|species| {
+                genome.distance(species.representative(), &self.config)
+                    < self.config.compatibility_threshold
+            });
+
+            match found {
+                Some(species) => species.members.push(idx),
+                None => {
+                    let mut species = Species::new(self.next_species_id, genome.clone());
+
+                    species.members.push(idx);
+                    self.species.push(species);
+                    self.next_species_id += 1;
+                }
+            }
+        }
+
+        self.species.retain(|species| !species.is_empty());
+    }
+
+    /// Splits `total` offspring proportionally to `shared` fitness, using
+    /// largest remainders; equally if every species has zero fitness.
+    fn allocate(shared: &[f32], total: usize) -> Vec<usize> {
+        let sum: f32 = shared.iter().sum();
+
+        let quotas: Vec<f32> = if sum > 0.0 {
+            shared.iter().map(|f| f / sum * total as f32).collect()
+        } else {
+            vec![total as f32 / shared.len() as f32; shared.len()]
+        };
+
+        let mut offspring: Vec<usize> = quotas.iter().map(|q| q.floor() as usize).collect();
+        let mut order: Vec<usize> = (0..quotas.len()).collect();
+
+        order.sort_by(|&a, &b| {
+            (quotas[b] - quotas[b].floor()).total_cmp(&(quotas[a] - quotas[a].floor()))
+        });
+
+        // Floored quotas never sum past `total`.
+        let missing = total - offspring.iter().sum::<usize>();
+
+        for &idx in order.iter().cycle().take(missing) {
+            offspring[idx] += 1;
+        }
+
+        offspring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    struct TestIndividual {
+        genome: Genome,
+    }
+
+    impl NeatIndividual for TestIndividual {
+        /// Rewards growing.
+        fn fitness(&self) -> f32 {
+            self.genome.connections().len() as f32
+        }
+
+        fn genome(&self) -> &Genome {
+            &self.genome
+        }
+
+        fn create(genome: Genome) -> Self {
+            Self { genome }
+        }
+    }
+
+    #[test]
+    fn test_allocate() {
+        assert_eq!(Neat::allocate(&[1.0, 1.0, 2.0], 10), vec![3, 2, 5]);
+        assert_eq!(Neat::allocate(&[0.0, 0.0, 0.0], 4), vec![2, 1, 1]);
+        assert_eq!(Neat::allocate(&[3.0], 7), vec![7]);
+    }
+
+    #[test]
+    fn test_evolve() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = NeatConfig {
+            add_node_chance: 0.3,
+            add_connection_chance: 0.3,
+            ..Default::default()
+        };
+        let mut neat = Neat::new(config, 3, 2);
+
+        let mut population: Vec<_> = (0..30)
+            .map(|_| TestIndividual::create(Genome::minimal(3, 2, &mut rng)))
+            .collect();
+
+        let first = Statistics::from_fitness(population.iter().map(|i| i.fitness()));
+
+        for _ in 0..20 {
+            population = neat.evolve(&mut rng, &population).0;
+
+            assert_eq!(population.len(), 30);
+        }
+
+        let last = Statistics::from_fitness(population.iter().map(|i| i.fitness()));
+
+        assert!(!neat.species().is_empty());
+        assert!(last.avg_fitness() > first.avg_fitness());
+    }
+
+    #[test]
+    fn test_speciate() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = NeatConfig {
+            compatibility_threshold: 0.5,
+            ..Default::default()
+        };
+        let mut neat = Neat::new(config, 3, 2);
+        let minimal = Genome::minimal(3, 2, &mut rng);
+        let mut grown = minimal.clone();
+
+        for _ in 0..6 {
+            grown.mutate_add_node(&mut rng, &mut neat.innovations);
+        }
+
+        let population = vec![
+            TestIndividual::create(minimal.clone()),
+            TestIndividual::create(grown),
+            TestIndividual::create(minimal),
+        ];
+
+        neat.speciate(&population);
+
+        assert_eq!(neat.species().len(), 2);
+        assert_eq!(neat.species()[0].members, vec![0, 2]);
+        assert_eq!(neat.species()[1].members, vec![1]);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::*;
+
+/// Phenotype of a `Genome`: its enabled connections evaluated in
+/// topological order.
+pub struct NeatNetwork {
+    inputs: usize,
+    /// Dense index of every output node, by node id.
+    outputs: Vec<usize>,
+    /// Non-input nodes in evaluation order.
+    steps: Vec<Step>,
+    /// `(source, weight)` of every step's incoming connections, back to back.
+    incoming: Vec<(usize, f32)>,
+    len: usize,
+}
+
+struct Step {
+    node: usize,
+    bias: f32,
+    activation: Activation,
+    incoming: std::ops::Range<usize>,
+}
+
+impl NeatNetwork {
+    /// Hidden nodes use `hidden_activation` and output nodes use
+    /// `output_activation`.
+    pub fn new(
+        genome: &Genome,
+        hidden_activation: Activation,
+        output_activation: Activation,
+    ) -> Self {
+        let nodes = genome.nodes();
+        let index = |id: usize| nodes.binary_search_by_key(&id, |node| node.id).unwrap();
+        let connections: Vec<_> = genome
+            .connections()
+            .iter()
+            .filter(|conn| conn.enabled)
+            .map(|conn| (index(conn.from), index(conn.to), conn.weight))
+            .collect();
+
+        // Kahn's algorithm; the genome guarantees there are no cycles.
+        let mut indegree = vec![0; nodes.len()];
+
+        for &(_, to, _) in &connections {
+            indegree[to] += 1;
+        }
+
+        let mut ready: Vec<_> = (0..nodes.len())
+            .filter(|&idx| indegree[idx] == 0)
+            .rev()
+            .collect();
+        let mut steps = Vec::with_capacity(nodes.len());
+        let mut incoming = Vec::with_capacity(connections.len());
+
+        while let Some(node) = ready.pop() {
+            if nodes[node].kind != NodeKind::Input {
+                let start = incoming.len();
+
+                incoming.extend(
+                    connections
+                        .iter()
+                        .filter(|(_, to, _)| *to == node)
+                        .map(|&(from, _, weight)| (from, weight)),
+                );
+
+                steps.push(Step {
+                    node,
+                    bias: nodes[node].bias,
+                    activation: match nodes[node].kind {
+                        NodeKind::Output => output_activation,
+                        _ => hidden_activation,
+                    },
+                    incoming: start..incoming.len(),
+                });
+            }
+
+            for &(from, to, _) in &connections {
+                if from == node {
+                    indegree[to] -= 1;
+
+                    if indegree[to] == 0 {
+                        ready.push(to);
+                    }
+                }
+            }
+        }
+
+        Self {
+            inputs: nodes
+                .iter()
+                .filter(|node| node.kind == NodeKind::Input)
+                .count(),
+            outputs: (0..nodes.len())
+                .filter(|&idx| nodes[idx].kind == NodeKind::Output)
+                .collect(),
+            steps,
+            incoming,
+            len: nodes.len(),
+        }
+    }
+
+    pub fn propagate(&mut self, input: DVector<f32>) -> DVector<f32> {
+        let mut out = DVector::zeros(self.outputs.len());
+
+        self.propagate_into(
+            input.as_slice(),
+            &mut Scratch::default(),
+            out.as_mut_slice(),
+        );
+
+        out
+    }
+
+    pub fn propagate_into(&self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        assert_eq!(input.len(), self.inputs);
+        assert_eq!(out.len(), self.outputs.len());
+
+        let (values, _) = scratch.buffers(self.len);
+
+        // Input nodes come first, since they have the lowest ids.
+        values[..self.inputs].copy_from_slice(input);
+
+        for step in &self.steps {
+            let sum = self.incoming[step.incoming.clone()]
+                .iter()
+                .fold(step.bias, |sum, &(from, weight)| {
+                    sum + weight * values[from]
+                });
+
+            values[step.node] = step.activation.apply(sum);
+        }
+
+        for (out, &idx) in out.iter_mut().zip(&self.outputs) {
+            *out = values[idx];
+        }
+    }
+}
+
+impl NeatNetwork {
+    /// The trace has a single layer holding every non-input node, by id.
+    pub fn propagate_traced(&self, input: &[f32]) -> Trace {
+        assert_eq!(input.len(), self.inputs);
+
+        let mut pre_activation = vec![0.0; self.len];
+        let mut values = vec![0.0; self.len];
+
+        values[..self.inputs].copy_from_slice(input);
+
+        for step in &self.steps {
+            let sum = self.incoming[step.incoming.clone()]
+                .iter()
+                .fold(step.bias, |sum, &(from, weight)| {
+                    sum + weight * values[from]
+                });
+
+            pre_activation[step.node] = sum;
+            values[step.node] = step.activation.apply(sum);
+        }
+
+        Trace {
+            layers: vec![LayerTrace {
+                pre_activation: pre_activation.split_off(self.inputs),
+                post_activation: values[self.inputs..].to_vec(),
+            }],
+            output: self.outputs.iter().map(|&idx| values[idx]).collect(),
+        }
+    }
+}
+
+impl Controller for NeatNetwork {
+    fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        NeatNetwork::propagate_into(self, input, scratch, out)
+    }
+
+    fn propagate_traced(&mut self, input: &[f32]) -> Trace {
+        NeatNetwork::propagate_traced(self, input)
+    }
+
+    fn reset_state(&mut self) {}
+
+    /// Biases and incoming weights of every non-input node, in evaluation
+    /// order. The structure lives in the `Genome`, so this is informational.
+    fn weights(&self) -> Box<dyn Iterator<Item = f32> + '_> {
+        Box::new(self.steps.iter().flat_map(|step| {
+            std::iter::once(step.bias).chain(
+                self.incoming[step.incoming.clone()]
+                    .iter()
+                    .map(|&(_, weight)| weight),
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_minimal() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let genome = Genome::minimal(2, 1, &mut rng);
+        let mut network = NeatNetwork::new(&genome, Activation::ReLU, Activation::Identity);
+
+        let actual = network.propagate(DVector::from_vec(vec![0.5, -1.0]));
+
+        let bias = genome.nodes()[2].bias;
+        let (w0, w1) = (
+            genome.connections()[0].weight,
+            genome.connections()[1].weight,
+        );
+
+        assert_relative_eq!(actual[0], bias + 0.5 * w0 - w1);
+    }
+
+    #[test]
+    fn test_add_node_preserves_output() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut genome = Genome::minimal(3, 2, &mut rng);
+        let mut innovations = Innovations::new(3, 2);
+        let input = DVector::from_vec(vec![0.2, 0.4, 0.6]);
+
+        let expected = NeatNetwork::new(&genome, Activation::Identity, Activation::Identity)
+            .propagate(input.clone());
+
+        for _ in 0..5 {
+            genome.mutate_add_node(&mut rng, &mut innovations);
+        }
+
+        let actual =
+            NeatNetwork::new(&genome, Activation::Identity, Activation::Identity).propagate(input);
+
+        assert_relative_eq!(actual.as_slice(), expected.as_slice(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_propagate_traced() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut genome = Genome::minimal(3, 2, &mut rng);
+        let mut innovations = Innovations::new(3, 2);
+
+        genome.mutate_add_node(&mut rng, &mut innovations);
+
+        let mut network = NeatNetwork::new(&genome, Activation::ReLU, Activation::Tanh);
+        let input = [0.2, -0.4, 0.6];
+
+        let actual = network.propagate_traced(&input);
+        let expected = network.propagate(DVector::from_vec(input.to_vec()));
+
+        assert_eq!(actual.layers[0].pre_activation.len(), 3);
+        assert_relative_eq!(actual.output.as_slice(), expected.as_slice());
+        assert_relative_eq!(&actual.layers[0].post_activation[..2], expected.as_slice());
+    }
+
+    #[test]
+    fn test_evaluation_order() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut genome = Genome::minimal(3, 2, &mut rng);
+        let mut innovations = Innovations::new(3, 2);
+
+        for _ in 0..10 {
+            genome.mutate_add_node(&mut rng, &mut innovations);
+            genome.mutate_add_connection(&mut rng, &mut innovations);
+        }
+
+        let network = NeatNetwork::new(&genome, Activation::Tanh, Activation::Tanh);
+
+        assert_eq!(network.steps.len(), genome.nodes().len() - 3);
+
+        for (pos, step) in network.steps.iter().enumerate() {
+            for &(from, _) in &network.incoming[step.incoming.clone()] {
+                let evaluated_before = network.steps[..pos].iter().any(|s| s.node == from);
+
+                assert!(from < 3 || evaluated_before);
+            }
+        }
+    }
+}
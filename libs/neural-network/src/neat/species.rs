@@ -0,0 +1,40 @@
+use crate::*;
+
+/// Genomes within `NeatConfig::compatibility_threshold` of a representative.
+#[derive(Clone, Debug)]
+pub struct Species {
+    id: usize,
+    representative: Genome,
+    /// Indices into the population being evolved.
+    pub(crate) members: Vec<usize>,
+}
+
+impl Species {
+    pub(crate) fn new(id: usize, representative: Genome) -> Self {
+        Self {
+            id,
+            representative,
+            members: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn representative(&self) -> &Genome {
+        &self.representative
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub(crate) fn set_representative(&mut self, representative: Genome) {
+        self.representative = representative;
+    }
+}
@@ -0,0 +1,296 @@
+use crate::*;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Plastic weights are kept within `-WEIGHT_LIMIT..=WEIGHT_LIMIT`, so that
+/// runaway Hebbian growth can't saturate a brain for good.
+pub const WEIGHT_LIMIT: f32 = 5.0;
+
+/// Lifetime learning rule of a network's (non-recurrent) weights, applied
+/// after every propagation from each connection's pre- and post-synaptic
+/// activations. Every connection evolves its own coefficients.
+#[derive(
+    IntoStaticStr, EnumIter, Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub enum Plasticity {
+    /// `dw = eta * pre * post`
+    Hebbian,
+    /// `dw = eta * (A * pre * post + B * pre + C * post + D)`
+    Abcd,
+}
+
+impl Plasticity {
+    /// Coefficients per connection.
+    pub fn genes(&self) -> usize {
+        match self {
+            Self::Hebbian => 1,
+            Self::Abcd => 5,
+        }
+    }
+}
+
+pub(crate) struct Plastic<S: Scalar = f32> {
+    pub(crate) rule: Plasticity,
+    /// Per layer, `rule.genes()` contiguous coefficients per weight, in the
+    /// order of the weight matrix.
+    pub(crate) coefficients: Vec<Vec<S>>,
+    /// The weights the network was created with; they're what gets
+    /// inherited, and what `reset_state` goes back to.
+    pub(crate) born: Vec<DMatrix<S>>,
+    reward: S,
+}
+
+impl<S: Scalar> Plastic<S> {
+    pub(crate) fn new(rule: Plasticity, coefficients: Vec<Vec<S>>, born: Vec<DMatrix<S>>) -> Self {
+        Self {
+            rule,
+            coefficients,
+            born,
+            reward: S::zero(),
+        }
+    }
+
+    /// Starts a propagation: returns the rule along with the scale of its
+    /// update, which uses up the pending reward.
+    pub(crate) fn begin(plastic: &mut Option<Self>) -> Option<(&Self, S)> {
+        plastic.as_mut().map(|plastic| {
+            let modulation = S::one() + plastic.reward;
+
+            plastic.reward = S::zero();
+            (&*plastic, modulation)
+        })
+    }
+
+    pub(crate) fn learn(
+        &self,
+        idx: usize,
+        layer: &mut Layer<S>,
+        input: &[S],
+        output: &[S],
+        modulation: S,
+    ) {
+        let genes = self.rule.genes();
+        let neurons = output.len();
+        let limit = scalar::<S>(WEIGHT_LIMIT);
+        let mask = layer.mask.as_ref();
+
+        for (i, (weight, coefficients)) in layer
+            .weights
+            .iter_mut()
+            .zip(self.coefficients[idx].chunks_exact(genes))
+            .enumerate()
+        {
+            if mask.is_some_and(|mask| !mask[i]) {
+                continue;
+            }
+
+            // Column-major: columns are inputs, rows are neurons.
+            let (pre, post) = (input[i / neurons], output[i % neurons]);
+
+            let delta = match self.rule {
+                Plasticity::Hebbian => coefficients[0] * pre * post,
+                Plasticity::Abcd => {
+                    coefficients[0]
+                        * (coefficients[1] * pre * post
+                            + coefficients[2] * pre
+                            + coefficients[3] * post
+                            + coefficients[4])
+                }
+            };
+
+            *weight = (*weight + modulation * delta).clamp(-limit, limit);
+        }
+    }
+}
+
+impl<S: Scalar> Network<S> {
+    /// Makes the network plastic, with random learning coefficients.
+    /// Learning rates start small, so that newborn brains behave much like
+    /// their static weights.
+    pub fn with_plasticity(mut self, rule: Plasticity, rng: &mut dyn RngCore) -> Self {
+        let coefficients = self
+            .layers
+            .iter()
+            .map(|layer| {
+                (0..layer.weights.len() * rule.genes())
+                    .map(|gene| {
+                        let range = if gene % rule.genes() == 0 { 0.1 } else { 1.0 };
+
+                        scalar(rng.gen_range(-range..range))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.plastic = Some(Plastic::new(rule, coefficients, self.born_weights()));
+        self
+    }
+
+    pub fn plasticity(&self) -> Option<Plasticity> {
+        self.plastic.as_ref().map(|plastic| plastic.rule)
+    }
+
+    /// Scales the next plastic update by `1 + reward`: positive rewards
+    /// reinforce what the network just did, negative ones weaken it.
+    pub fn reward(&mut self, reward: S) {
+        if let Some(plastic) = &mut self.plastic {
+            plastic.reward += reward;
+        }
+    }
+
+    /// Makes the current weights the ones that get inherited.
+    pub(crate) fn settle_weights(&mut self) {
+        let born = self.born_weights();
+
+        if let Some(plastic) = &mut self.plastic {
+            plastic.born = born;
+        }
+    }
+
+    fn born_weights(&self) -> Vec<DMatrix<S>> {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const TOPOLOGY: [LayerTopology; 2] = [
+        LayerTopology {
+            neurons: 2,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+        },
+        LayerTopology {
+            neurons: 1,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+        },
+    ];
+
+    /// `bias, w0, w1, coefficients...`
+    fn network(rule: Plasticity, coefficients: &[f32]) -> Network {
+        let weights = [0.0, 0.5, -0.5].iter().chain(coefficients).copied();
+
+        Network::from_plastic_weights(&TOPOLOGY, Some(rule), weights)
+    }
+
+    #[test]
+    fn test_hebbian() {
+        let mut network = network(Plasticity::Hebbian, &[0.1, 0.2]);
+
+        // post = 0.5 * 1 - 0.5 * 2 = -0.5
+        network.propagate(DVector::from_vec(vec![1.0, 2.0]));
+
+        let actual: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        assert_relative_eq!(actual.as_slice(), [0.45, -0.7].as_slice());
+    }
+
+    #[test]
+    fn test_abcd() {
+        let mut network = network(
+            Plasticity::Abcd,
+            &[1.0, 0.0, 0.0, 0.0, 0.25, 0.5, 1.0, 1.0, 1.0, 0.0],
+        );
+
+        // post = -0.5
+        network.propagate(DVector::from_vec(vec![1.0, 2.0]));
+
+        let actual: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        // w0 += 0.25, w1 += 0.5 * (-1 + 2 - 0.5)
+        assert_relative_eq!(actual.as_slice(), [0.75, -0.25].as_slice());
+    }
+
+    #[test]
+    fn test_reward() {
+        let mut network = network(Plasticity::Hebbian, &[0.1, 0.2]);
+
+        network.reward(1.0);
+        network.propagate(DVector::from_vec(vec![1.0, 2.0]));
+
+        let actual: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        assert_relative_eq!(actual.as_slice(), [0.4, -0.9].as_slice());
+
+        // The reward only applies once.
+        network.propagate(DVector::from_vec(vec![0.0, 0.0]));
+
+        let actual: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        assert_relative_eq!(actual.as_slice(), [0.4, -0.9].as_slice());
+    }
+
+    #[test]
+    fn test_weight_limit() {
+        let mut network = network(
+            Plasticity::Abcd,
+            &[1.0, 0.0, 0.0, 0.0, 10.0, 1.0, 0.0, 0.0, 0.0, -10.0],
+        );
+
+        network.propagate(DVector::from_vec(vec![1.0, 1.0]));
+
+        let actual: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        assert_relative_eq!(actual.as_slice(), [WEIGHT_LIMIT, -WEIGHT_LIMIT].as_slice());
+    }
+
+    #[test]
+    fn test_weights_are_inherited_unlearned() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network: Network =
+            Network::random(&TOPOLOGY, &mut rng).with_plasticity(Plasticity::Abcd, &mut rng);
+        let expected: Vec<_> = network.weights().collect();
+
+        for _ in 0..5 {
+            network.propagate(DVector::from_vec(vec![1.0, -1.0]));
+        }
+
+        let learned: Vec<_> = network.layers[0].weights.iter().copied().collect();
+        let actual: Vec<_> = network.weights().collect();
+
+        assert_eq!(actual.len(), 1 + 2 + 2 * 5);
+        assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        assert_relative_eq!(
+            Network::from_plastic_weights(&TOPOLOGY, Some(Plasticity::Abcd), actual)
+                .weights()
+                .collect::<Vec<_>>()
+                .as_slice(),
+            expected.as_slice()
+        );
+
+        network.reset_state();
+
+        let reset: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        assert_relative_eq!(reset.as_slice(), &expected[1..3]);
+        assert!(learned != reset);
+    }
+
+    #[test]
+    fn test_try_from_plastic_weights() {
+        assert!(matches!(
+            Network::<f32>::try_from_plastic_weights(
+                &TOPOLOGY,
+                Some(Plasticity::Hebbian),
+                vec![0.0; 4]
+            ),
+            Err(NetworkError::TooFewWeights(5, 4))
+        ));
+        assert!(matches!(
+            Network::<f32>::try_from_plastic_weights(
+                &TOPOLOGY,
+                Some(Plasticity::Hebbian),
+                vec![0.0; 6]
+            ),
+            Err(NetworkError::LeftoverWeights(5))
+        ));
+    }
+}
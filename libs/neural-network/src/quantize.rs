@@ -0,0 +1,416 @@
+use crate::*;
+
+/// `Network` with int8 weights, one scale per layer. Inputs to every layer
+/// are quantized on the fly, so the products accumulate in integers; only
+/// biases and activations stay in floating point.
+pub struct QuantizedNetwork {
+    layers: Vec<QuantizedLayer>,
+    /// Quantized inputs of the layer being evaluated.
+    buffer: Vec<i8>,
+}
+
+struct QuantizedLayer {
+    /// Row-major, `neurons x inputs`.
+    weights: Vec<i8>,
+    /// Row-major, `neurons x context`.
+    recurrent: Vec<i8>,
+    /// Shared by `weights` and `recurrent`.
+    scale: f32,
+    biases: Vec<f32>,
+    activation: Activation,
+    kind: LayerKind,
+    inputs: usize,
+    context: Vec<f32>,
+}
+
+/// How far a `QuantizedNetwork` strays from the float network it was made
+/// from, over every output of every sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QuantizationReport {
+    pub samples: usize,
+    pub mean_abs_error: f32,
+    pub max_abs_error: f32,
+    pub rmse: f32,
+}
+
+impl Network {
+    /// The quantized network starts with blank recurrent state, and is
+    /// never plastic: it gets the current weights, learned or not.
+    pub fn quantize(&self) -> QuantizedNetwork {
+        QuantizedNetwork {
+            layers: self.layers.iter().map(QuantizedLayer::new).collect(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl QuantizedNetwork {
+    pub fn propagate(&mut self, input: DVector<f32>) -> DVector<f32> {
+        let mut out = DVector::zeros(self.layers.last().unwrap().biases.len());
+
+        self.propagate_into(
+            input.as_slice(),
+            &mut Scratch::default(),
+            out.as_mut_slice(),
+        );
+
+        out
+    }
+
+    /// Advances the state of any recurrent layers.
+    pub fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        assert_eq!(input.len(), self.layers[0].inputs);
+        assert_eq!(out.len(), self.layers.last().unwrap().biases.len());
+
+        let width = self.layers.iter().map(|layer| layer.biases.len()).max();
+        let (front, back) = scratch.buffers(width.unwrap_or(0).max(input.len()));
+
+        front[..input.len()].copy_from_slice(input);
+
+        for layer in &mut self.layers {
+            let (input, output) = (&front[..layer.inputs], &mut back[..layer.biases.len()]);
+
+            layer.propagate(input, output, &mut self.buffer);
+
+            if layer.kind == LayerKind::Elman {
+                layer.context.copy_from_slice(output);
+            }
+
+            std::mem::swap(front, back);
+        }
+
+        out.copy_from_slice(&front[..out.len()]);
+
+        for layer in &mut self.layers {
+            if layer.kind == LayerKind::Jordan {
+                layer.context.copy_from_slice(out);
+            }
+        }
+    }
+
+    pub fn reset_state(&mut self) {
+        for layer in &mut self.layers {
+            layer.context.fill(0.0);
+        }
+    }
+
+    /// Dequantized parameters, in `Network::weights` order.
+    pub fn weights(&self) -> impl Iterator<Item = f32> + '_ {
+        self.layers.iter().flat_map(|layer| {
+            let neurons = layer.biases.len();
+
+            layer
+                .biases
+                .iter()
+                .copied()
+                .chain(dequantize(&layer.weights, neurons, layer.scale))
+                .chain(dequantize(&layer.recurrent, neurons, layer.scale))
+        })
+    }
+
+    /// Feeds `inputs` in order through both networks, from blank state,
+    /// and compares the outputs. `network` is left alone: a copy of its
+    /// current weights is run instead, which doesn't learn even if
+    /// `network` is plastic, just like the quantized one.
+    pub fn compare(&mut self, network: &Network, inputs: &[DVector<f32>]) -> QuantizationReport {
+        let mut network = Network {
+            layers: network.layers.clone(),
+            plastic: None,
+        };
+
+        self.reset_state();
+        network.reset_state();
+
+        let mut report = QuantizationReport {
+            samples: inputs.len(),
+            ..Default::default()
+        };
+        let (mut outputs, mut sum, mut sum_squared) = (0, 0.0, 0.0);
+
+        for input in inputs {
+            let expected = network.propagate(input.clone());
+            let actual = self.propagate(input.clone());
+
+            for error in (actual - expected).iter().map(|error| error.abs()) {
+                report.max_abs_error = report.max_abs_error.max(error);
+                sum += error;
+                sum_squared += error * error;
+                outputs += 1;
+            }
+        }
+
+        if outputs > 0 {
+            report.mean_abs_error = sum / outputs as f32;
+            report.rmse = (sum_squared / outputs as f32).sqrt();
+        }
+
+        report
+    }
+}
+
+impl Controller for QuantizedNetwork {
+    fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        QuantizedNetwork::propagate_into(self, input, scratch, out)
+    }
+
+    /// Reports the dequantized pre-activations.
+    fn propagate_traced(&mut self, input: &[f32]) -> Trace {
+        let mut trace = Trace::default();
+        let mut input = input.to_vec();
+
+        for idx in 0..self.layers.len() {
+            let layer = &mut self.layers[idx];
+            let mut output = vec![0.0; layer.biases.len()];
+
+            layer.pre_activation(&input, &mut output, &mut self.buffer);
+
+            let layer_trace = LayerTrace::new(output, layer.activation);
+
+            if layer.kind == LayerKind::Elman {
+                layer.context.copy_from_slice(&layer_trace.post_activation);
+            }
+
+            input = layer_trace.post_activation.clone();
+            trace.layers.push(layer_trace);
+        }
+
+        for layer in &mut self.layers {
+            if layer.kind == LayerKind::Jordan {
+                layer.context.copy_from_slice(&input);
+            }
+        }
+
+        trace.output = input;
+        trace
+    }
+
+    fn reset_state(&mut self) {
+        QuantizedNetwork::reset_state(self)
+    }
+
+    fn weights(&self) -> Box<dyn Iterator<Item = f32> + '_> {
+        Box::new(QuantizedNetwork::weights(self))
+    }
+}
+
+impl QuantizedLayer {
+    fn new(layer: &Layer) -> Self {
+        let max = layer
+            .weights
+            .iter()
+            .chain(layer.recurrent.iter())
+            .fold(0.0f32, |max, w| max.max(w.abs()));
+        let scale = scale(max);
+        let quantize = |matrix: &DMatrix<f32>| {
+            // Row-major, so that each neuron's weights are contiguous.
+            matrix
+                .row_iter()
+                .flat_map(|row| row.iter().map(|&w| quantize(w, scale)).collect::<Vec<_>>())
+                .collect()
+        };
+
+        Self {
+            weights: quantize(&layer.weights),
+            recurrent: quantize(&layer.recurrent),
+            scale,
+            biases: layer.biases.iter().copied().collect(),
+            activation: layer.activation,
+            kind: layer.kind,
+            inputs: layer.weights.ncols(),
+            context: vec![0.0; layer.context.len()],
+        }
+    }
+
+    fn propagate(&self, input: &[f32], output: &mut [f32], buffer: &mut Vec<i8>) {
+        self.pre_activation(input, output, buffer);
+
+        for y in output {
+            *y = self.activation.apply(*y);
+        }
+    }
+
+    fn pre_activation(&self, input: &[f32], output: &mut [f32], buffer: &mut Vec<i8>) {
+        output.copy_from_slice(&self.biases);
+
+        accumulate(&self.weights, self.scale, input, output, buffer);
+
+        if !self.context.is_empty() {
+            accumulate(&self.recurrent, self.scale, &self.context, output, buffer);
+        }
+    }
+}
+
+/// `output += weights * input`, with `input` quantized into `buffer` and
+/// the products summed as integers.
+fn accumulate(
+    weights: &[i8],
+    weight_scale: f32,
+    input: &[f32],
+    output: &mut [f32],
+    buffer: &mut Vec<i8>,
+) {
+    let input_scale = scale(input.iter().fold(0.0f32, |max, x| max.max(x.abs())));
+
+    buffer.clear();
+    buffer.extend(input.iter().map(|&x| quantize(x, input_scale)));
+
+    for (y, row) in output.iter_mut().zip(weights.chunks_exact(input.len())) {
+        let sum: i32 = row
+            .iter()
+            .zip(buffer.iter())
+            .map(|(&w, &x)| w as i32 * x as i32)
+            .sum();
+
+        *y += sum as f32 * weight_scale * input_scale;
+    }
+}
+
+/// Column-major floats out of row-major `weights` with `rows` rows.
+fn dequantize(weights: &[i8], rows: usize, scale: f32) -> impl Iterator<Item = f32> + '_ {
+    let cols = weights.len() / rows.max(1);
+
+    (0..cols)
+        .flat_map(move |col| (0..rows).map(move |row| weights[row * cols + col] as f32 * scale))
+}
+
+/// Maps `-max..=max` onto `-127..=127`.
+fn scale(max: f32) -> f32 {
+    if max > 0.0 {
+        max / 127.0
+    } else {
+        1.0
+    }
+}
+
+fn quantize(x: f32, scale: f32) -> i8 {
+    (x / scale).round().clamp(-127.0, 127.0) as i8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn layers(kind: LayerKind) -> [LayerTopology; 3] {
+        [
+            LayerTopology {
+                neurons: 9,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 9,
+                activation: Activation::ReLU,
+                kind,
+            },
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ]
+    }
+
+    /// Vision-like inputs: mostly empty cells with a few bright ones.
+    fn inputs(rng: &mut dyn RngCore) -> Vec<DVector<f32>> {
+        (0..100)
+            .map(|_| {
+                DVector::from_fn(9, |_, _| {
+                    if rng.gen_bool(0.3) {
+                        rng.gen_range(0.0..1.5)
+                    } else {
+                        0.0
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantize() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network: Network = Network::random(&layers(LayerKind::Dense), &mut rng);
+        let quantized = network.quantize();
+
+        let expected: Vec<_> = network.weights().collect();
+        let actual: Vec<_> = quantized.weights().collect();
+
+        assert_eq!(actual.len(), expected.len());
+        assert_relative_eq!(
+            actual.as_slice(),
+            expected.as_slice(),
+            epsilon = 1.0 / 254.0
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        for kind in [LayerKind::Dense, LayerKind::Elman, LayerKind::Jordan] {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let network: Network =
+                Network::random_with(&layers(kind), &Initializer::Xavier, &mut rng);
+            let mut quantized = network.quantize();
+
+            let report = quantized.compare(&network, &inputs(&mut rng));
+
+            assert_eq!(report.samples, 100);
+            assert!(report.mean_abs_error > 0.0);
+            assert!(report.mean_abs_error <= report.rmse);
+            assert!(report.rmse <= report.max_abs_error);
+            assert!(report.max_abs_error < 0.1, "{kind:?}: {report:?}");
+        }
+    }
+
+    #[test]
+    fn test_compare_plastic() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network =
+            Network::random_with(&layers(LayerKind::Dense), &Initializer::Xavier, &mut rng)
+                .with_plasticity(Plasticity::Hebbian, &mut rng);
+        let mut quantized = network.quantize();
+        let before: Vec<_> = network.weights().collect();
+
+        let report = quantized.compare(&network, &inputs(&mut rng));
+        let after: Vec<_> = network.weights().collect();
+
+        assert!(report.max_abs_error < 0.1, "{report:?}");
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_zero_input() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network: Network = Network::random(&layers(LayerKind::Dense), &mut rng);
+        let mut quantized = network.quantize();
+        let input = DVector::zeros(9);
+
+        assert_relative_eq!(
+            quantized.propagate(input.clone()).as_slice(),
+            network.propagate(input).as_slice(),
+            epsilon = 0.02
+        );
+    }
+
+    #[test]
+    fn test_controller() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let network: Network = Network::random(&layers(LayerKind::Elman), &mut rng);
+        let mut quantized = network.quantize();
+        let mut controller: Box<dyn Controller> = Box::new(network.quantize());
+        let input = inputs(&mut rng).remove(0);
+        let mut actual = [0.0; 2];
+
+        controller.propagate_into(input.as_slice(), &mut Scratch::default(), &mut actual);
+
+        let traced = controller.propagate_traced(input.as_slice());
+        let expected = quantized.propagate(input.clone());
+
+        assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        assert_relative_eq!(
+            traced.output.as_slice(),
+            quantized.propagate(input).as_slice()
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use nalgebra::RealField;
+use std::cmp::Ordering;
+
+/// Number type of a `Network`'s weights and activations: `f32` by default.
+/// `f64` networks can be built, from weights too, and propagated, e.g. to
+/// compare against `f32` results; evolving, training, saving them and
+/// running them as a `Controller` is `f32`-only.
+pub trait Scalar: RealField + Copy {}
+
+impl<T> Scalar for T where T: RealField + Copy {}
+
+/// Converts configuration values, which are always `f32`.
+pub(crate) fn scalar<S: Scalar>(x: f32) -> S {
+    nalgebra::convert(x as f64)
+}
+
+/// `f64::total_cmp`, so that NaN weights sort instead of panicking.
+pub(crate) fn total_cmp<S: Scalar>(a: S, b: S) -> Ordering {
+    nalgebra::convert_unchecked::<S, f64>(a).total_cmp(&nalgebra::convert_unchecked(b))
+}
@@ -0,0 +1,167 @@
+use crate::*;
+use std::mem;
+
+/// Two ping-pong buffers wide enough for the widest layer, reused by
+/// `Network::propagate_into` so that it never allocates.
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    front: Vec<f32>,
+    back: Vec<f32>,
+}
+
+impl Scratch {
+    pub fn new(layers: &[LayerTopology]) -> Self {
+        let width = layers.iter().map(|layer| layer.neurons).max().unwrap_or(0);
+
+        Self {
+            front: vec![0.0; width],
+            back: vec![0.0; width],
+        }
+    }
+
+    /// Grows both buffers to at least `width` and hands them out.
+    pub(crate) fn buffers(&mut self, width: usize) -> (&mut Vec<f32>, &mut Vec<f32>) {
+        if self.back.len() < width {
+            self.front.resize(width, 0.0);
+            self.back.resize(width, 0.0);
+        }
+
+        (&mut self.front, &mut self.back)
+    }
+}
+
+impl Network {
+    /// Same as `propagate`, but reads from and writes to caller-owned
+    /// slices. `scratch` only grows if it's narrower than this network.
+    pub fn propagate_into(&mut self, input: &[f32], scratch: &mut Scratch, out: &mut [f32]) {
+        let (last, hidden) = self.layers.split_last_mut().unwrap();
+
+        assert_eq!(input.len(), hidden.first().unwrap_or(last).weights.ncols());
+        assert_eq!(out.len(), last.biases.len());
+
+        let width = hidden
+            .iter()
+            .map(|layer| layer.biases.len())
+            .max()
+            .unwrap_or(0);
+
+        let (mut front, mut back) = scratch.buffers(width);
+        let mut src = input;
+        let plastic = Plastic::begin(&mut self.plastic);
+
+        for (idx, layer) in hidden.iter_mut().enumerate() {
+            let n = layer.biases.len();
+
+            layer.propagate_column(
+                DVectorView::from_slice(src, src.len()),
+                DVectorViewMut::from_slice(&mut back[..n], n),
+            );
+
+            if let Some((plastic, modulation)) = plastic {
+                plastic.learn(idx, layer, src, &back[..n], modulation);
+            }
+
+            layer.remember_own(&back[..n]);
+
+            mem::swap(&mut front, &mut back);
+            src = &front[..n];
+        }
+
+        last.propagate_column(
+            DVectorView::from_slice(src, src.len()),
+            DVectorViewMut::from_slice(out, out.len()),
+        );
+
+        if let Some((plastic, modulation)) = plastic {
+            plastic.learn(hidden.len(), last, src, out, modulation);
+        }
+
+        last.remember_own(out);
+
+        self.remember_output(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const TOPOLOGY: [LayerTopology; 4] = [
+        LayerTopology {
+            neurons: 3,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+        },
+        LayerTopology {
+            neurons: 5,
+            activation: Activation::ReLU,
+            kind: LayerKind::Dense,
+        },
+        LayerTopology {
+            neurons: 4,
+            activation: Activation::Sigmoid,
+            kind: LayerKind::Dense,
+        },
+        LayerTopology {
+            neurons: 2,
+            activation: Activation::Tanh,
+            kind: LayerKind::Dense,
+        },
+    ];
+
+    #[test]
+    fn test_propagate_into() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&TOPOLOGY, &mut rng);
+        let mut scratch = Scratch::new(&TOPOLOGY);
+        let input = [0.3, -0.7, 1.1];
+        let mut actual = [0.0; 2];
+
+        network.propagate_into(&input, &mut scratch, &mut actual);
+
+        let expected = network.propagate(DVector::from_vec(input.to_vec()));
+
+        assert_relative_eq!(actual.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_propagate_into_recurrent() {
+        let mut layers = TOPOLOGY;
+        layers[1].kind = LayerKind::Elman;
+        layers[2].kind = LayerKind::Jordan;
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&layers, &mut rng);
+        let mut expected_network = Network::from_weights(&layers, network.weights());
+        let mut scratch = Scratch::new(&layers);
+        let input = [0.3, -0.7, 1.1];
+        let mut actual = [0.0; 2];
+
+        for _ in 0..3 {
+            network.propagate_into(&input, &mut scratch, &mut actual);
+
+            let expected = expected_network.propagate(DVector::from_vec(input.to_vec()));
+
+            assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_propagate_into_grows_scratch() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&TOPOLOGY, &mut rng);
+        let mut scratch = Scratch::default();
+        let input = [0.3, -0.7, 1.1];
+        let mut actual = [0.0; 2];
+
+        network.propagate_into(&input, &mut scratch, &mut actual);
+
+        let expected = network.propagate(DVector::from_vec(input.to_vec()));
+
+        assert_relative_eq!(actual.as_slice(), expected.as_slice());
+        assert_eq!(scratch.front.len(), 5);
+    }
+}
@@ -0,0 +1,492 @@
+use crate::*;
+
+/// Compact form of `Network::weights` for networks with disconnected
+/// weights: which input weights are connected, as packed bits, and then
+/// only the values that are actually used.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SparseWeights<S = f32> {
+    /// One bit per input weight of every layer, in `Network::weights` order.
+    pub connections: Vec<u64>,
+    /// `Network::weights` without the disconnected weights.
+    pub values: Vec<S>,
+}
+
+impl<S: Scalar> Network<S> {
+    /// Number of connected input weights; recurrent weights are always
+    /// connected.
+    pub fn connections(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| match &layer.mask {
+                Some(mask) => mask.iter().filter(|&&connected| connected).count(),
+                None => layer.weights.len(),
+            })
+            .sum()
+    }
+
+    /// Fraction of input weights that are disconnected.
+    pub fn sparsity(&self) -> f32 {
+        let total = self.input_weights();
+
+        if total == 0 {
+            0.0
+        } else {
+            1.0 - self.connections() as f32 / total as f32
+        }
+    }
+
+    /// Whether `input` feeds `neuron` of the `layer`-th non-input layer.
+    pub fn is_connected(&self, layer: usize, neuron: usize, input: usize) -> bool {
+        let layer = &self.layers[layer];
+
+        layer.is_connected(neuron + input * layer.weights.nrows())
+    }
+
+    /// Disconnecting a weight zeroes it, so reconnected weights start from
+    /// zero.
+    pub fn set_connected(&mut self, layer: usize, neuron: usize, input: usize, connected: bool) {
+        let idx = neuron + input * self.layers[layer].weights.nrows();
+
+        self.set_connected_at(layer, idx, connected);
+    }
+
+    /// Mutation: disconnects every connected weight with
+    /// `disconnect_chance` and reconnects every disconnected one with
+    /// `connect_chance`, so that sparsity drifts towards
+    /// `disconnect_chance / (disconnect_chance + connect_chance)`.
+    pub fn mutate_connections(
+        &mut self,
+        rng: &mut dyn RngCore,
+        disconnect_chance: f32,
+        connect_chance: f32,
+    ) {
+        assert!((0.0..=1.0).contains(&disconnect_chance));
+        assert!((0.0..=1.0).contains(&connect_chance));
+
+        for layer in 0..self.layers.len() {
+            for idx in 0..self.layers[layer].weights.len() {
+                let connected = self.layers[layer].is_connected(idx);
+                let chance = if connected {
+                    disconnect_chance
+                } else {
+                    connect_chance
+                };
+
+                if rng.gen_bool(chance as f64) {
+                    self.set_connected_at(layer, idx, !connected);
+                }
+            }
+        }
+    }
+
+    /// Mutation: flips a single input weight, picked uniformly.
+    pub fn toggle_connection(&mut self, rng: &mut dyn RngCore) {
+        let total = self.input_weights();
+
+        if total == 0 {
+            return;
+        }
+
+        let mut idx = rng.gen_range(0..total);
+
+        for layer in 0..self.layers.len() {
+            let len = self.layers[layer].weights.len();
+
+            if idx < len {
+                let connected = self.layers[layer].is_connected(idx);
+
+                self.set_connected_at(layer, idx, !connected);
+                return;
+            }
+
+            idx -= len;
+        }
+    }
+
+    /// Disconnects the smallest weights of every layer, by magnitude, until
+    /// at least `sparsity` of it is disconnected. Layers are pruned on their
+    /// own, so that narrow ones aren't cut off entirely; nothing gets
+    /// reconnected.
+    pub fn prune(&mut self, sparsity: f32) {
+        assert!((0.0..=1.0).contains(&sparsity));
+
+        for layer_idx in 0..self.layers.len() {
+            let layer = &self.layers[layer_idx];
+            let len = layer.weights.len();
+            let target = (sparsity * len as f32).round() as usize;
+            let mut connected: Vec<_> = (0..len).filter(|&idx| layer.is_connected(idx)).collect();
+            let disconnected = len - connected.len();
+
+            if target <= disconnected {
+                continue;
+            }
+
+            connected.sort_by(|&a, &b| total_cmp(layer.weights[a].abs(), layer.weights[b].abs()));
+
+            for idx in connected.into_iter().take(target - disconnected) {
+                self.set_connected_at(layer_idx, idx, false);
+            }
+        }
+    }
+
+    /// Like `weights`, but leaves out the disconnected weights and keeps
+    /// track of which ones they were.
+    pub fn sparse_weights(&self) -> SparseWeights<S> {
+        let keep = self
+            .layers
+            .iter()
+            .flat_map(|layer| {
+                std::iter::repeat_n(true, layer.biases.len())
+                    .chain((0..layer.weights.len()).map(|idx| layer.is_connected(idx)))
+                    .chain(std::iter::repeat_n(true, layer.recurrent.len()))
+            })
+            .chain(std::iter::repeat(true));
+
+        SparseWeights {
+            connections: pack(self.connection_flags()),
+            values: self
+                .weights()
+                .zip(keep)
+                .filter_map(|(weight, keep)| keep.then_some(weight))
+                .collect(),
+        }
+    }
+
+    pub fn from_sparse_weights(
+        layers: &[LayerTopology],
+        plasticity: Option<Plasticity>,
+        weights: &SparseWeights<S>,
+    ) -> Self {
+        Self::try_from_sparse_weights(layers, plasticity, weights).unwrap()
+    }
+
+    /// Counterpart of `sparse_weights`; see `try_from_plastic_weights`.
+    pub fn try_from_sparse_weights(
+        layers: &[LayerTopology],
+        plasticity: Option<Plasticity>,
+        weights: &SparseWeights<S>,
+    ) -> Result<Self, NetworkError> {
+        if layers.len() < 2 {
+            return Err(NetworkError::TooFewLayers(layers.len()));
+        }
+
+        let output_size = layers[layers.len() - 1].neurons;
+        let input_weights = layers
+            .array_windows::<2>()
+            .try_fold(0usize, |sum, [fst, snd]| {
+                fst.neurons.checked_mul(snd.neurons)?.checked_add(sum)
+            })
+            .ok_or(NetworkError::TooLarge)?;
+
+        check_connections(&weights.connections, input_weights)?;
+
+        let mut values = weights.values.iter().copied();
+        let mut dense = Vec::with_capacity(weights.values.len());
+        let mut bit = 0;
+
+        for [fst, snd] in layers.array_windows::<2>() {
+            dense.extend(values.by_ref().take(snd.neurons));
+
+            for _ in 0..fst.neurons * snd.neurons {
+                if is_set(&weights.connections, bit) {
+                    dense.extend(values.next());
+                } else {
+                    dense.push(S::zero());
+                }
+
+                bit += 1;
+            }
+
+            dense.extend(
+                values
+                    .by_ref()
+                    .take(snd.context_size(output_size) * snd.neurons),
+            );
+        }
+
+        dense.extend(values);
+
+        let mut network = Self::try_from_plastic_weights(layers, plasticity, dense)?;
+
+        network.try_set_connections(&weights.connections)?;
+
+        Ok(network)
+    }
+
+    /// Packed connection bits, as in `SparseWeights`; `None` while fully
+    /// connected.
+    pub fn connection_bits(&self) -> Option<Vec<u64>> {
+        self.layers
+            .iter()
+            .any(|layer| layer.mask.is_some())
+            .then(|| pack(self.connection_flags()))
+    }
+
+    /// Counterpart of `connection_bits`; disconnected weights are zeroed.
+    pub fn try_set_connections(&mut self, bits: &[u64]) -> Result<(), NetworkError> {
+        check_connections(bits, self.input_weights())?;
+
+        let mut bit = 0;
+
+        for layer in 0..self.layers.len() {
+            for idx in 0..self.layers[layer].weights.len() {
+                self.set_connected_at(layer, idx, is_set(bits, bit));
+                bit += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_connected_at(&mut self, layer_idx: usize, idx: usize, connected: bool) {
+        let layer = &mut self.layers[layer_idx];
+
+        if layer.is_connected(idx) == connected {
+            return;
+        }
+
+        let (rows, cols) = layer.weights.shape();
+        let mask = layer
+            .mask
+            .get_or_insert_with(|| DMatrix::repeat(rows, cols, true));
+
+        mask[idx] = connected;
+
+        if mask.iter().all(|&connected| connected) {
+            layer.mask = None;
+        }
+
+        layer.weights[idx] = S::zero();
+
+        if let Some(plastic) = &mut self.plastic {
+            plastic.born[layer_idx][idx] = S::zero();
+        }
+    }
+
+    fn connection_flags(&self) -> impl Iterator<Item = bool> + '_ {
+        self.layers
+            .iter()
+            .flat_map(|layer| (0..layer.weights.len()).map(|idx| layer.is_connected(idx)))
+    }
+
+    fn input_weights(&self) -> usize {
+        self.layers.iter().map(|layer| layer.weights.len()).sum()
+    }
+}
+
+fn pack(flags: impl Iterator<Item = bool>) -> Vec<u64> {
+    let mut bits = Vec::new();
+
+    for (idx, flag) in flags.enumerate() {
+        if idx % 64 == 0 {
+            bits.push(0);
+        }
+
+        if flag {
+            bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+
+    bits
+}
+
+fn is_set(bits: &[u64], idx: usize) -> bool {
+    bits[idx / 64] >> (idx % 64) & 1 == 1
+}
+
+fn check_connections(bits: &[u64], input_weights: usize) -> Result<(), NetworkError> {
+    if bits.len() == input_weights.div_ceil(64) {
+        Ok(())
+    } else {
+        Err(NetworkError::ConnectionsMismatch(input_weights, bits.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn layers(kind: LayerKind) -> [LayerTopology; 3] {
+        [
+            LayerTopology {
+                neurons: 6,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 12,
+                activation: Activation::Tanh,
+                kind,
+            },
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Tanh,
+                kind: LayerKind::Dense,
+            },
+        ]
+    }
+
+    fn network(kind: LayerKind) -> Network {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        Network::random(&layers(kind), &mut rng)
+    }
+
+    #[test]
+    fn test_set_connected() {
+        let mut network = network(LayerKind::Dense);
+        let input = DVector::from_vec(vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+
+        network.set_connected(1, 0, 3, false);
+
+        let mut expected = Network::from_weights(&layers(LayerKind::Dense), network.weights());
+
+        assert!(!network.is_connected(1, 0, 3));
+        assert_eq!(network.connections(), 6 * 12 + 12 * 2 - 1);
+        assert_relative_eq!(network.layers[1].weights[(0, 3)], 0.0);
+        assert_relative_eq!(
+            network.propagate(input.clone()).as_slice(),
+            expected.propagate(input).as_slice()
+        );
+
+        network.set_connected(1, 0, 3, true);
+
+        assert!(network.is_connected(1, 0, 3));
+        assert!(network.layers[1].mask.is_none());
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut network = network(LayerKind::Dense);
+        let smallest = network.layers[0]
+            .weights
+            .iter()
+            .map(|weight| weight.abs())
+            .fold(f32::INFINITY, f32::min);
+        let before: Vec<_> = network.layers[0].weights.iter().copied().collect();
+
+        network.prune(0.75);
+
+        assert_relative_eq!(network.sparsity(), 0.75);
+        assert_eq!(network.connections(), 18 + 6);
+
+        // The kept weights are untouched and all at least as large as the
+        // pruned ones.
+        let kept: Vec<_> = (0..72)
+            .filter(|&idx| network.layers[0].is_connected(idx))
+            .collect();
+        let largest_pruned = (0..72)
+            .filter(|&idx| !network.layers[0].is_connected(idx))
+            .map(|idx| before[idx].abs())
+            .fold(smallest, f32::max);
+
+        for idx in kept {
+            assert_eq!(network.layers[0].weights[idx], before[idx]);
+            assert!(before[idx].abs() >= largest_pruned);
+        }
+
+        // Pruning to a lower sparsity doesn't reconnect anything.
+        network.prune(0.5);
+
+        assert_relative_eq!(network.sparsity(), 0.75);
+    }
+
+    #[test]
+    fn test_prune_nan() {
+        let mut network = network(LayerKind::Dense);
+
+        network.layers[0].weights[3] = f32::NAN;
+        network.prune(0.5);
+
+        assert_relative_eq!(network.sparsity(), 0.5);
+    }
+
+    #[test]
+    fn test_mutate_connections() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(LayerKind::Dense);
+
+        for _ in 0..50 {
+            network.mutate_connections(&mut rng, 0.1, 0.3);
+        }
+
+        assert!(
+            (network.sparsity() - 0.25).abs() < 0.1,
+            "{}",
+            network.sparsity()
+        );
+
+        let connections = network.connections();
+
+        network.toggle_connection(&mut rng);
+
+        assert_eq!(connections.abs_diff(network.connections()), 1);
+    }
+
+    #[test]
+    fn test_sparse_weights() {
+        for kind in [LayerKind::Dense, LayerKind::Elman, LayerKind::Jordan] {
+            let mut rng = ChaCha8Rng::from_seed(Default::default());
+            let mut network: Network = Network::random(&layers(kind), &mut rng)
+                .with_plasticity(Plasticity::Hebbian, &mut rng);
+
+            network.prune(0.5);
+
+            let sparse = network.sparse_weights();
+            let actual =
+                Network::from_sparse_weights(&layers(kind), Some(Plasticity::Hebbian), &sparse);
+
+            assert_eq!(sparse.connections.len(), 2);
+            assert_eq!(
+                sparse.values.len(),
+                network.weights().count() - (6 * 12 + 12 * 2) / 2
+            );
+            assert_eq!(
+                actual.weights().collect::<Vec<_>>(),
+                network.weights().collect::<Vec<_>>()
+            );
+            assert_eq!(actual.connection_bits(), network.connection_bits());
+        }
+    }
+
+    #[test]
+    fn test_try_from_sparse_weights_mismatch() {
+        let mut sparse = network(LayerKind::Dense).sparse_weights();
+
+        sparse.connections.push(0);
+
+        assert!(matches!(
+            Network::<f32>::try_from_sparse_weights(&layers(LayerKind::Dense), None, &sparse),
+            Err(NetworkError::ConnectionsMismatch(96, 3))
+        ));
+    }
+
+    #[test]
+    fn test_disconnected_stay_zero() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = network(LayerKind::Dense).with_plasticity(Plasticity::Abcd, &mut rng);
+        let mut trainer = Trainer::new(Optimizer::Sgd(0.1), 1);
+        let samples = [(
+            DVector::from_vec(vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6]),
+            DVector::from_vec(vec![0.5, -0.5]),
+        )];
+
+        network.prune(0.5);
+
+        for _ in 0..10 {
+            network.propagate(samples[0].0.clone());
+            trainer.train_epoch(&mut rng, &mut network, &samples);
+        }
+
+        for layer in &network.layers {
+            for idx in 0..layer.weights.len() {
+                if !layer.is_connected(idx) {
+                    assert_eq!(layer.weights[idx], 0.0);
+                }
+            }
+        }
+    }
+}
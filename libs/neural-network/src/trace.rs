@@ -0,0 +1,119 @@
+use crate::*;
+
+/// What every neuron did during one propagation.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Trace {
+    /// One entry per layer that computes something, i.e. the input layer is
+    /// left out.
+    pub layers: Vec<LayerTrace>,
+    pub output: Vec<f32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LayerTrace {
+    /// Weighted sums plus biases, before the activation function.
+    pub pre_activation: Vec<f32>,
+    pub post_activation: Vec<f32>,
+}
+
+impl LayerTrace {
+    pub(crate) fn new(pre_activation: Vec<f32>, activation: Activation) -> Self {
+        Self {
+            post_activation: pre_activation
+                .iter()
+                .map(|&x| activation.apply(x))
+                .collect(),
+            pre_activation,
+        }
+    }
+}
+
+impl Network {
+    /// Like `propagate`, but also keeps every layer's intermediate values.
+    pub fn propagate_traced(&mut self, input: DVector<f32>) -> Trace {
+        let mut layers = Vec::with_capacity(self.layers.len());
+
+        let plastic = Plastic::begin(&mut self.plastic);
+
+        let output = self
+            .layers
+            .iter_mut()
+            .enumerate()
+            .fold(input, |input, (idx, layer)| {
+                let trace =
+                    LayerTrace::new(layer.pre_activation(&input).data.into(), layer.activation);
+                let output = DVector::from_column_slice(&trace.post_activation);
+
+                if let Some((plastic, modulation)) = plastic {
+                    plastic.learn(idx, layer, input.as_slice(), output.as_slice(), modulation);
+                }
+
+                layer.remember_own(output.as_slice());
+                layers.push(trace);
+
+                output
+            });
+
+        self.remember_output(output.as_slice());
+
+        Trace {
+            layers,
+            output: output.data.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const TOPOLOGY: [LayerTopology; 3] = [
+        LayerTopology {
+            neurons: 3,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+        },
+        LayerTopology {
+            neurons: 4,
+            activation: Activation::ReLU,
+            kind: LayerKind::Elman,
+        },
+        LayerTopology {
+            neurons: 2,
+            activation: Activation::Tanh,
+            kind: LayerKind::Dense,
+        },
+    ];
+
+    #[test]
+    fn test_propagate_traced() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&TOPOLOGY, &mut rng);
+        let mut expected = Network::from_weights(&TOPOLOGY, network.weights());
+        let input = DVector::from_vec(vec![0.3, -0.7, 1.1]);
+
+        for _ in 0..3 {
+            let actual = network.propagate_traced(input.clone());
+            let expected = expected.propagate(input.clone());
+
+            assert_eq!(actual.layers.len(), 2);
+            assert_eq!(actual.layers[0].pre_activation.len(), 4);
+            assert_relative_eq!(actual.output.as_slice(), expected.as_slice());
+            assert_relative_eq!(
+                actual.layers[1].post_activation.as_slice(),
+                expected.as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn test_layer_trace() {
+        let actual = LayerTrace::new(vec![-1.0, 2.0], Activation::ReLU);
+
+        assert_relative_eq!(actual.pre_activation.as_slice(), [-1.0, 2.0].as_slice());
+        assert_relative_eq!(actual.post_activation.as_slice(), [0.0, 2.0].as_slice());
+    }
+}
@@ -0,0 +1,413 @@
+use crate::*;
+use rand::seq::SliceRandom;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(IntoStaticStr, EnumIter, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Optimizer {
+    /// Plain stochastic gradient descent.
+    /// (learning_rate)
+    Sgd(f32),
+    /// (learning_rate, beta1, beta2, epsilon)
+    Adam(f32, f32, f32, f32),
+}
+
+impl Optimizer {
+    /// Adam with the usual `beta1 = 0.9`, `beta2 = 0.999`, `epsilon = 1e-8`.
+    pub fn adam(learning_rate: f32) -> Self {
+        Self::Adam(learning_rate, 0.9, 0.999, 1e-8)
+    }
+}
+
+/// Mean squared error over every output of every sample.
+pub fn mse(output: &DMatrix<f32>, target: &DMatrix<f32>) -> f32 {
+    assert_eq!(output.shape(), target.shape());
+
+    (output - target).norm_squared() / output.len().max(1) as f32
+}
+
+/// Trains a `Network` by backpropagation on `(input, target)` pairs,
+/// minimizing `mse`.
+///
+/// Samples are treated as independent: recurrent context is held at zero,
+/// so recurrent weights are left untouched and the network's state isn't
+/// advanced. Disconnected weights stay at zero. Plastic networks don't
+/// learn while being trained, and keep the trained weights as the ones
+/// they pass on.
+pub struct Trainer {
+    optimizer: Optimizer,
+    batch_size: usize,
+    /// Adam's first and second moments, per layer; started over whenever
+    /// the trainer meets a network of another shape.
+    moments: Vec<Moments>,
+    steps: i32,
+}
+
+struct Moments {
+    weights: (DMatrix<f32>, DMatrix<f32>),
+    biases: (DVector<f32>, DVector<f32>),
+}
+
+/// Gradients of the loss with respect to one layer's parameters.
+struct Gradients {
+    weights: DMatrix<f32>,
+    biases: DVector<f32>,
+}
+
+impl Trainer {
+    pub fn new(optimizer: Optimizer, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+
+        Self {
+            optimizer,
+            batch_size,
+            moments: Vec::new(),
+            steps: 0,
+        }
+    }
+
+    /// One pass over `samples` in random order, in mini-batches. Returns
+    /// the mean loss of the batches, measured before each update.
+    pub fn train_epoch(
+        &mut self,
+        rng: &mut dyn RngCore,
+        network: &mut Network,
+        samples: &[(DVector<f32>, DVector<f32>)],
+    ) -> f32 {
+        assert!(!samples.is_empty());
+
+        let mut order: Vec<_> = (0..samples.len()).collect();
+        let mut loss = 0.0;
+
+        order.shuffle(rng);
+
+        for batch in order.chunks(self.batch_size) {
+            let input = DMatrix::from_columns(
+                &batch
+                    .iter()
+                    .map(|&idx| samples[idx].0.clone())
+                    .collect::<Vec<_>>(),
+            );
+            let target = DMatrix::from_columns(
+                &batch
+                    .iter()
+                    .map(|&idx| samples[idx].1.clone())
+                    .collect::<Vec<_>>(),
+            );
+
+            loss += self.train_batch(network, &input, &target) * batch.len() as f32;
+        }
+
+        loss / samples.len() as f32
+    }
+
+    /// One update from a batch with one sample per column. Returns the loss
+    /// before the update.
+    pub fn train_batch(
+        &mut self,
+        network: &mut Network,
+        input: &DMatrix<f32>,
+        target: &DMatrix<f32>,
+    ) -> f32 {
+        assert_eq!(input.ncols(), target.ncols());
+
+        let (pre_activations, activations) = Self::forward(network, input);
+        let output = activations.last().unwrap();
+        let loss = mse(output, target);
+
+        // d(mse)/d(output)
+        let mut delta = (output - target) * (2.0 / output.len() as f32);
+        let mut gradients = Vec::with_capacity(network.layers.len());
+
+        for (idx, layer) in network.layers.iter().enumerate().rev() {
+            let pre_activation = &pre_activations[idx];
+
+            delta.zip_apply(pre_activation, |d, z| *d *= layer.activation.derivative(z));
+
+            gradients.push(Gradients {
+                weights: &delta * activations[idx].transpose(),
+                biases: delta.column_sum(),
+            });
+
+            if idx > 0 {
+                delta = layer.weights.transpose() * &delta;
+            }
+        }
+
+        gradients.reverse();
+        self.apply(network, gradients);
+
+        for layer in &mut network.layers {
+            layer.apply_mask();
+        }
+
+        network.settle_weights();
+
+        loss
+    }
+
+    /// Pre-activations of every layer, and activations of every layer
+    /// including the input.
+    fn forward(network: &Network, input: &DMatrix<f32>) -> (Vec<DMatrix<f32>>, Vec<DMatrix<f32>>) {
+        let mut pre_activations = Vec::with_capacity(network.layers.len());
+        let mut activations = vec![input.clone()];
+
+        for layer in &network.layers {
+            let mut z = &layer.weights * activations.last().unwrap();
+
+            for mut column in z.column_iter_mut() {
+                column += &layer.biases;
+            }
+
+            activations.push(z.map(|x| layer.activation.apply(x)));
+            pre_activations.push(z);
+        }
+
+        (pre_activations, activations)
+    }
+
+    fn apply(&mut self, network: &mut Network, gradients: Vec<Gradients>) {
+        self.steps += 1;
+
+        match self.optimizer {
+            Optimizer::Sgd(learning_rate) => {
+                for (layer, gradients) in network.layers.iter_mut().zip(gradients) {
+                    layer.weights -= gradients.weights * learning_rate;
+                    layer.biases -= gradients.biases * learning_rate;
+                }
+            }
+
+            Optimizer::Adam(learning_rate, beta1, beta2, epsilon) => {
+                let same_shape = self.moments.len() == network.layers.len()
+                    && self
+                        .moments
+                        .iter()
+                        .zip(&network.layers)
+                        .all(|(moments, layer)| {
+                            moments.weights.0.shape() == layer.weights.shape()
+                                && moments.biases.0.len() == layer.biases.len()
+                        });
+
+                if !same_shape {
+                    self.steps = 1;
+                    self.moments = network
+                        .layers
+                        .iter()
+                        .map(|layer| {
+                            let weights =
+                                DMatrix::zeros(layer.weights.nrows(), layer.weights.ncols());
+                            let biases = DVector::zeros(layer.biases.len());
+
+                            Moments {
+                                weights: (weights.clone(), weights),
+                                biases: (biases.clone(), biases),
+                            }
+                        })
+                        .collect();
+                }
+
+                let step_size = learning_rate * (1.0 - beta2.powi(self.steps)).sqrt()
+                    / (1.0 - beta1.powi(self.steps));
+
+                for ((layer, gradients), moments) in network
+                    .layers
+                    .iter_mut()
+                    .zip(gradients)
+                    .zip(&mut self.moments)
+                {
+                    adam(
+                        layer.weights.as_mut_slice(),
+                        gradients.weights.as_slice(),
+                        (
+                            moments.weights.0.as_mut_slice(),
+                            moments.weights.1.as_mut_slice(),
+                        ),
+                        (beta1, beta2, epsilon, step_size),
+                    );
+                    adam(
+                        layer.biases.as_mut_slice(),
+                        gradients.biases.as_slice(),
+                        (
+                            moments.biases.0.as_mut_slice(),
+                            moments.biases.1.as_mut_slice(),
+                        ),
+                        (beta1, beta2, epsilon, step_size),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn adam(
+    params: &mut [f32],
+    gradients: &[f32],
+    (m, v): (&mut [f32], &mut [f32]),
+    (beta1, beta2, epsilon, step_size): (f32, f32, f32, f32),
+) {
+    for (((param, &grad), m), v) in params.iter_mut().zip(gradients).zip(m).zip(v) {
+        *m = beta1 * *m + (1.0 - beta1) * grad;
+        *v = beta2 * *v + (1.0 - beta2) * grad * grad;
+        *param -= step_size * *m / (v.sqrt() + epsilon);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use strum::IntoEnumIterator;
+
+    fn topology(activation: Activation) -> [LayerTopology; 3] {
+        [
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 8,
+                activation,
+                kind: LayerKind::Dense,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Identity,
+                kind: LayerKind::Dense,
+            },
+        ]
+    }
+
+    /// `y = x0 * x1` on a grid over -1..1.
+    fn samples() -> Vec<(DVector<f32>, DVector<f32>)> {
+        let grid = (-4..=4).map(|x| x as f32 / 4.0);
+
+        grid.clone()
+            .flat_map(|x0| grid.clone().map(move |x1| (x0, x1)))
+            .map(|(x0, x1)| {
+                (
+                    DVector::from_vec(vec![x0, x1]),
+                    DVector::from_vec(vec![x0 * x1]),
+                )
+            })
+            .collect()
+    }
+
+    fn train(optimizer: Optimizer, epochs: usize) -> (f32, f32) {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&topology(Activation::Tanh), &mut rng);
+        let mut trainer = Trainer::new(optimizer, 8);
+        let samples = samples();
+
+        let first = trainer.train_epoch(&mut rng, &mut network, &samples);
+        let last = (1..epochs)
+            .map(|_| trainer.train_epoch(&mut rng, &mut network, &samples))
+            .last()
+            .unwrap();
+
+        (first, last)
+    }
+
+    #[test]
+    fn test_sgd() {
+        let (first, last) = train(Optimizer::Sgd(0.1), 300);
+
+        assert!(last < first / 4.0, "{first} -> {last}");
+    }
+
+    #[test]
+    fn test_adam() {
+        let (first, last) = train(Optimizer::adam(0.01), 300);
+
+        assert!(last < 0.01, "{first} -> {last}");
+    }
+
+    /// Reusing a trainer on a wider network of the same depth still
+    /// updates every weight.
+    #[test]
+    fn test_adam_other_shape() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut trainer = Trainer::new(Optimizer::adam(0.01), 8);
+        let samples = samples();
+
+        let mut narrow = Network::random(&topology(Activation::Tanh), &mut rng);
+        trainer.train_epoch(&mut rng, &mut narrow, &samples);
+
+        let mut topology = topology(Activation::Tanh);
+        topology[1].neurons = 16;
+
+        let mut wide = Network::random(&topology, &mut rng);
+        let before: Vec<_> = wide.weights().collect();
+        trainer.train_epoch(&mut rng, &mut wide, &samples);
+
+        assert!(wide
+            .weights()
+            .zip(before)
+            .all(|(after, before)| after != before));
+    }
+
+    /// Backpropagated gradients match finite differences for every
+    /// activation.
+    #[test]
+    fn test_gradients() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let input = DMatrix::from_column_slice(2, 3, &[0.3, -0.7, 0.9, 0.1, -0.4, -0.8]);
+        let target = DMatrix::from_row_slice(1, 3, &[0.5, -0.2, 0.1]);
+        let loss =
+            |network: &Network| mse(Trainer::forward(network, &input).1.last().unwrap(), &target);
+
+        for activation in Activation::iter().chain([Activation::LeakyReLU(0.1)]) {
+            let network = Network::random(&topology(activation), &mut rng);
+            let weights: Vec<_> = network.weights().collect();
+
+            let mut trained = Network::from_weights(&topology(activation), weights.clone());
+            let mut trainer = Trainer::new(Optimizer::Sgd(1.0), 3);
+
+            trainer.train_batch(&mut trained, &input, &target);
+
+            // With a learning rate of 1, the update is the negated gradient.
+            let actual: Vec<_> = weights
+                .iter()
+                .zip(trained.weights())
+                .map(|(before, after)| before - after)
+                .collect();
+
+            let h = 1e-3;
+            let expected: Vec<_> = (0..weights.len())
+                .map(|idx| {
+                    let mut plus = weights.clone();
+                    let mut minus = weights.clone();
+
+                    plus[idx] += h;
+                    minus[idx] -= h;
+
+                    (loss(&Network::from_weights(&topology(activation), plus))
+                        - loss(&Network::from_weights(&topology(activation), minus)))
+                        / (2.0 * h)
+                })
+                .collect();
+
+            assert_relative_eq!(actual.as_slice(), expected.as_slice(), epsilon = 2e-3);
+        }
+    }
+
+    #[test]
+    fn test_chromosome_roundtrip() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::random(&topology(Activation::ReLU), &mut rng);
+        let mut trainer = Trainer::new(Optimizer::adam(0.01), 4);
+
+        trainer.train_epoch(&mut rng, &mut network, &samples());
+
+        let chromosome: Chromosome = network.weights().collect();
+        let mut restored =
+            Network::from_weights(&topology(Activation::ReLU), chromosome.iter().copied());
+        let input = DVector::from_vec(vec![0.5, -0.25]);
+
+        assert_relative_eq!(
+            restored.propagate(input.clone()).as_slice(),
+            network.propagate(input).as_slice()
+        );
+    }
+}
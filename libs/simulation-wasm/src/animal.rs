@@ -6,6 +6,10 @@ pub struct Animal {
     pub y: f32,
     pub rot: f32,
     pub speed: f32,
+    pub elite: bool,
+    pub vision: Vec<f32>,
+    /// Only set when the simulation runs with `brain_traced`.
+    pub trace: Option<nn::Trace>,
 }
 
 impl From<&sim::Animal> for Animal {
@@ -15,6 +19,9 @@ impl From<&sim::Animal> for Animal {
             y: animal.position().y,
             rot: animal.rot().angle(),
             speed: animal.speed(),
+            elite: animal.is_elite(),
+            vision: animal.vision().to_vec(),
+            trace: animal.trace().cloned(),
         }
     }
 }
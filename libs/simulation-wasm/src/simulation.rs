@@ -15,12 +15,12 @@ pub struct Simulation {
 #[wasm_bindgen]
 impl Simulation {
     #[wasm_bindgen(constructor)]
-    pub fn new(config: JsValue) -> Self {
+    pub fn new(config: JsValue) -> Result<Simulation, JsError> {
         console_error_panic_hook::set_once();
 
-        let config: sim::Config = serde_wasm_bindgen::from_value(config).unwrap();
+        let config: sim::Config = serde_wasm_bindgen::from_value(config)?;
         let mut rng = thread_rng();
-        let sim = sim::Simulation::random(&mut rng, config);
+        let sim = sim::Simulation::try_random(&mut rng, config)?;
         let crossovermethods = nn::geneticalgorithm::Crossover::iter()
             .map(|x| x.into())
             .collect();
@@ -31,13 +31,13 @@ impl Simulation {
             .map(|x| x.into())
             .collect();
 
-        Self {
+        Ok(Self {
             rng,
             sim,
             crossovermethods,
             selectionmethods,
             mutationmethods,
-        }
+        })
     }
 
     pub fn default_config() -> JsValue {
@@ -61,22 +61,54 @@ impl Simulation {
         serde_wasm_bindgen::to_value(&self.crossovermethods).unwrap()
     }
 
+    /// The `Crossover::presets` entry called `name`, ready to be put in a
+    /// config.
+    pub fn crossover_method(name: &str) -> Result<JsValue, JsError> {
+        preset(nn::Crossover::presets(), "crossover", name)
+    }
+
     pub fn selection_methods(&self) -> JsValue {
         serde_wasm_bindgen::to_value(&self.selectionmethods).unwrap()
     }
 
+    /// The `Selection::presets` entry called `name`, ready to be put in a
+    /// config.
+    pub fn selection_method(name: &str) -> Result<JsValue, JsError> {
+        preset(nn::Selection::presets(), "selection", name)
+    }
+
     pub fn mutation_methods(&self) -> JsValue {
         serde_wasm_bindgen::to_value(&self.mutationmethods).unwrap()
     }
 
-    pub fn train(&mut self) -> String {
+    /// The `Mutation::presets` entry called `name`, ready to be put in a
+    /// config.
+    pub fn mutation_method(name: &str) -> Result<JsValue, JsError> {
+        preset(nn::Mutation::presets(), "mutation", name)
+    }
+
+    /// `nn::Statistics` of the generation that just finished.
+    pub fn train(&mut self) -> JsValue {
         let stats = self.sim.train(&mut self.rng);
 
-        format!(
-            "Min. Fitness = {:.2}</br>Max. Fitness = {:.2}</br>Avg. Fitness = {:.2}",
-            stats.min_fitness(),
-            stats.max_fitness(),
-            stats.avg_fitness()
-        )
+        serde_wasm_bindgen::to_value(&stats).unwrap()
     }
 }
+
+/// Looks `name` up among `presets` by variant name; `kind` is only for the
+/// error message.
+fn preset<T>(
+    presets: impl IntoIterator<Item = T>,
+    kind: &str,
+    name: &str,
+) -> Result<JsValue, JsError>
+where
+    T: Serialize + Into<&'static str> + Copy,
+{
+    let method = presets
+        .into_iter()
+        .find(|&method| method.into() == name)
+        .ok_or_else(|| JsError::new(&format!("unknown {kind} method: {name}")))?;
+
+    Ok(serde_wasm_bindgen::to_value(&method)?)
+}
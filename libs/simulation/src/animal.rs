@@ -7,6 +7,13 @@ pub struct Animal {
     pub(crate) eye: Eye,
     pub(crate) brain: Brain,
     pub(crate) collisions: usize,
+    pub(crate) elite: bool,
+    /// Fitness carried over by an elite that isn't re-evaluated, used
+    /// instead of `collisions`.
+    pub(crate) kept_fitness: Option<f32>,
+    vision: Vec<f32>,
+    scratch: nn::Scratch,
+    trace: Option<nn::Trace>,
 }
 
 impl Animal {
@@ -28,24 +35,85 @@ impl Animal {
         self.speed
     }
 
+    pub fn brain(&self) -> &Brain {
+        &self.brain
+    }
+
+    /// Copied unchanged from the previous generation, see
+    /// `Config::elite_count`.
+    pub fn is_elite(&self) -> bool {
+        self.elite
+    }
+
     pub(crate) fn as_chromosome(&self) -> nn::Chromosome {
         self.brain.as_chromosome()
     }
 
+    /// Elites keep their connections, everyone else's are mutated.
     pub(crate) fn from_chromosome(
         chromosome: nn::Chromosome,
+        elite: bool,
         rng: &mut dyn RngCore,
         config: &Config,
     ) -> Self {
-        let brain = Brain::from_chromosome(chromosome, config);
+        let brain = Brain::from_chromosome(chromosome, config, (!elite).then_some(&mut *rng));
+        let mut animal = Self::new(config, brain, rng);
+
+        animal.elite = elite;
+        animal
+    }
+
+    /// Reinforces what a plastic brain just did.
+    pub(crate) fn reward(&mut self, reward: f32) {
+        self.brain.nn.reward(reward);
+    }
+
+    pub(crate) fn as_genome(&self) -> &nn::Genome {
+        self.brain
+            .genome
+            .as_ref()
+            .expect("only NEAT brains have a genome")
+    }
+
+    pub(crate) fn from_genome(genome: nn::Genome, rng: &mut dyn RngCore, config: &Config) -> Self {
+        let brain = Brain::from_genome(genome, config);
 
         Self::new(config, brain, rng)
     }
 
+    /// What the eye saw during the last step.
+    pub fn vision(&self) -> &[f32] {
+        &self.vision
+    }
+
+    /// What the brain did during the last step; only kept when
+    /// `Config::brain_traced` is set.
+    pub fn trace(&self) -> Option<&nn::Trace> {
+        self.trace.as_ref()
+    }
+
     pub(crate) fn process_brain(&mut self, food: &[Food], config: &Config) {
-        let vision = self.eye.process_vision(self.pos, self.rot, food);
+        let mut response = [0.0; 2];
+
+        self.vision.fill(0.0);
+        self.eye
+            .process_vision_into(self.pos, self.rot, food, &mut self.vision);
+
+        if config.brain_traced {
+            let trace = self.brain.nn.propagate_traced(&self.vision);
+
+            response.copy_from_slice(&trace.output);
+            self.trace = Some(trace);
+        } else {
+            self.brain
+                .nn
+                .propagate_into(&self.vision, &mut self.scratch, &mut response);
+        }
+
+        self.process_response(&response, config);
+    }
 
-        let response = self.brain.nn.propagate(vision);
+    pub(crate) fn process_response(&mut self, response: &[f32], config: &Config) {
         let speed = response[0].clamp(-config.speed_accel, config.speed_accel);
         let rot = response[1].clamp(-config.rot_accel, config.rot_accel);
 
@@ -66,8 +134,13 @@ impl Animal {
             rot: rng.gen(),
             speed: config.speed_max,
             eye: Eye::new(config),
+            vision: vec![0.0; config.eye_cells],
+            scratch: Default::default(),
+            trace: None,
             brain,
             collisions: 0,
+            elite: false,
+            kept_fitness: None,
         }
     }
 }
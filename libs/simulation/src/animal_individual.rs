@@ -3,6 +3,9 @@ use crate::*;
 pub struct AnimalIndividual {
     fitness: f32,
     chromosome: nn::Chromosome,
+    elite: bool,
+    /// Set for elites that aren't re-evaluated.
+    kept_fitness: Option<f32>,
 }
 
 impl nn::Individual for AnimalIndividual {
@@ -18,6 +21,17 @@ impl nn::Individual for AnimalIndividual {
         Self {
             fitness: 0.0,
             chromosome,
+            elite: false,
+            kept_fitness: None,
+        }
+    }
+
+    fn create_elite(chromosome: nn::Chromosome, fitness: Option<f32>) -> Self {
+        Self {
+            fitness: 0.0,
+            chromosome,
+            elite: true,
+            kept_fitness: fitness,
         }
     }
 }
@@ -25,12 +39,52 @@ impl nn::Individual for AnimalIndividual {
 impl AnimalIndividual {
     pub fn from_animal(animal: &Animal) -> Self {
         Self {
-            fitness: animal.collisions as f32,
+            fitness: animal.kept_fitness.unwrap_or(animal.collisions as f32),
             chromosome: animal.as_chromosome(),
+            elite: false,
+            kept_fitness: None,
+        }
+    }
+
+    pub fn into_animal(self, rng: &mut dyn RngCore, config: &Config) -> Animal {
+        let mut animal = Animal::from_chromosome(self.chromosome, self.elite, rng, config);
+
+        animal.kept_fitness = self.kept_fitness;
+        animal
+    }
+}
+
+pub struct AnimalNeatIndividual {
+    fitness: f32,
+    genome: nn::Genome,
+}
+
+impl nn::NeatIndividual for AnimalNeatIndividual {
+    fn fitness(&self) -> f32 {
+        self.fitness
+    }
+
+    fn genome(&self) -> &nn::Genome {
+        &self.genome
+    }
+
+    fn create(genome: nn::Genome) -> Self {
+        Self {
+            fitness: 0.0,
+            genome,
+        }
+    }
+}
+
+impl AnimalNeatIndividual {
+    pub fn from_animal(animal: &Animal) -> Self {
+        Self {
+            fitness: animal.collisions as f32,
+            genome: animal.as_genome().clone(),
         }
     }
 
     pub fn into_animal(self, rng: &mut dyn RngCore, config: &Config) -> Animal {
-        Animal::from_chromosome(self.chromosome, rng, config)
+        Animal::from_genome(self.genome, rng, config)
     }
 }
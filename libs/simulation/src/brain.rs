@@ -1,23 +1,176 @@
 use crate::*;
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub enum BrainType {
+    FeedForward,
+    /// Continuous-time recurrent network; every neuron uses
+    /// `Config::brain_output_activation`.
+    Ctrnn,
+    /// Topology evolves too, see `Config::neat`; `Config::brain_neurons`
+    /// and `Config::brain_kind` are ignored.
+    Neat,
+}
+
 pub struct Brain {
-    pub(crate) nn: nn::Network,
+    pub(crate) nn: Box<dyn nn::Controller>,
+    /// Set for `BrainType::Neat`, whose structure isn't captured by weights.
+    pub(crate) genome: Option<nn::Genome>,
+    /// Step sizes of `nn::Mutation::SelfAdaptive`; carried over to the next
+    /// generation, but not part of the network.
+    pub(crate) strategy: Vec<f32>,
 }
 
 impl Brain {
     pub fn random(config: &Config, rng: &mut dyn RngCore) -> Self {
+        let nn: Box<dyn nn::Controller> = match config.brain_type {
+            BrainType::FeedForward => {
+                let network = nn::Network::random_with(
+                    &Self::topology(config),
+                    &config.brain_initializer,
+                    rng,
+                );
+
+                match config.brain_plasticity {
+                    Some(rule) => Box::new(network.with_plasticity(rule, rng)),
+                    None => Box::new(network),
+                }
+            }
+            BrainType::Ctrnn => Box::new(nn::Ctrnn::random(Self::ctrnn_topology(config), rng)),
+            BrainType::Neat => {
+                return Self::from_genome(nn::Genome::minimal(config.eye_cells, 2, rng), config)
+            }
+        };
+
+        Self {
+            nn,
+            genome: None,
+            strategy: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_genome(genome: nn::Genome, config: &Config) -> Self {
+        let nn = nn::NeatNetwork::new(
+            &genome,
+            config.brain_activation,
+            config.brain_output_activation,
+        );
+
         Self {
-            nn: nn::Network::random(&Self::topology(config), rng),
+            nn: Box::new(nn),
+            genome: Some(genome),
+            strategy: Vec::new(),
         }
     }
 
     pub(crate) fn as_chromosome(&self) -> nn::Chromosome {
-        self.nn.weights().collect()
+        let connections = self
+            .nn
+            .as_network()
+            .and_then(nn::Network::connection_bits)
+            .unwrap_or_default();
+
+        self.nn
+            .weights()
+            .collect::<nn::Chromosome>()
+            .with_strategy(self.strategy.clone())
+            .with_connections(connections)
     }
 
-    pub(crate) fn from_chromosome(chromosome: nn::Chromosome, config: &Config) -> Self {
+    /// With `rng`, the connections are mutated as well, see
+    /// `Config::brain_disconnect_chance`.
+    pub(crate) fn from_chromosome(
+        chromosome: nn::Chromosome,
+        config: &Config,
+        rng: Option<&mut dyn RngCore>,
+    ) -> Self {
+        let strategy = chromosome.strategy().to_vec();
+        let nn: Box<dyn nn::Controller> = match config.brain_type {
+            BrainType::FeedForward => {
+                let connections = chromosome.connections().to_vec();
+                let mut network = nn::Network::from_plastic_weights(
+                    &Self::topology(config),
+                    config.brain_plasticity,
+                    chromosome,
+                );
+
+                // Zeroes the disconnected weights again, so that mutation
+                // can't bring them back as ordinary genes.
+                if !connections.is_empty() {
+                    network
+                        .try_set_connections(&connections)
+                        .expect("connections don't match the brain");
+                }
+
+                let (disconnect, connect) =
+                    (config.brain_disconnect_chance, config.brain_connect_chance);
+
+                if let Some(rng) = rng.filter(|_| disconnect > 0.0 || connect > 0.0) {
+                    network.mutate_connections(rng, disconnect, connect);
+                }
+
+                Box::new(network)
+            }
+            BrainType::Ctrnn => Box::new(nn::Ctrnn::from_weights(
+                Self::ctrnn_topology(config),
+                chromosome,
+            )),
+            BrainType::Neat => panic!("NEAT brains are created from genomes"),
+        };
+
         Self {
-            nn: nn::Network::from_weights(&Self::topology(config), chromosome),
+            nn,
+            genome: None,
+            strategy,
+        }
+    }
+
+    /// Names the eye cells by the direction they look at, relative to the
+    /// animal's heading.
+    pub fn neuron_names(config: &Config) -> nn::NeuronNames {
+        let cell_fov = config.eye_fov / config.eye_cells as f32;
+
+        nn::NeuronNames {
+            inputs: (0..config.eye_cells)
+                .map(|cell| {
+                    let angle = -config.eye_fov / 2.0 + (cell as f32 + 0.5) * cell_fov;
+
+                    format!("eye {cell} ({:+.0}°)", angle.to_degrees())
+                })
+                .collect(),
+            outputs: vec!["speed".into(), "rotation".into()],
+        }
+    }
+
+    /// `None` unless this is a `BrainType::FeedForward` brain.
+    pub fn to_dot(&self, config: &Config) -> Option<String> {
+        Some(self.nn.as_network()?.to_dot(&Self::neuron_names(config)))
+    }
+
+    /// `None` unless this is a `BrainType::FeedForward` brain.
+    pub fn to_svg(&self, config: &Config) -> Option<String> {
+        Some(self.nn.as_network()?.to_svg(&Self::neuron_names(config)))
+    }
+
+    /// How chromosomes map onto the brain, for `nn::Crossover::Neuron` and
+    /// `nn::Crossover::Layer`; `None` unless brains are
+    /// `BrainType::FeedForward`.
+    pub fn layout(config: &Config) -> Option<nn::NetworkLayout> {
+        match config.brain_type {
+            BrainType::FeedForward => Some(nn::NetworkLayout::new(
+                &Self::topology(config),
+                config.brain_plasticity,
+            )),
+            _ => None,
+        }
+    }
+
+    fn ctrnn_topology(config: &Config) -> nn::CtrnnTopology {
+        nn::CtrnnTopology {
+            inputs: config.eye_cells,
+            neurons: config.brain_neurons + 2,
+            outputs: 2,
+            activation: config.brain_output_activation,
+            dt: config.brain_dt,
         }
     }
 
@@ -25,11 +178,19 @@ impl Brain {
         [
             nn::LayerTopology {
                 neurons: config.eye_cells,
+                activation: nn::Activation::Identity,
+                kind: nn::LayerKind::Dense,
             },
             nn::LayerTopology {
                 neurons: config.brain_neurons,
+                activation: config.brain_activation,
+                kind: config.brain_kind,
+            },
+            nn::LayerTopology {
+                neurons: 2,
+                activation: config.brain_output_activation,
+                kind: nn::LayerKind::Dense,
             },
-            nn::LayerTopology { neurons: 2 },
         ]
     }
 }
@@ -1,12 +1,38 @@
 use crate::*;
 
+/// Fields missing from serialized configs, e.g. ones saved before the field
+/// existed, take their `Config::default()` values.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct Config {
     pub eye_fov: f32,
     pub eye_range: f32,
     pub eye_cells: usize,
 
+    pub brain_type: BrainType,
     pub brain_neurons: usize,
+    pub brain_activation: nn::Activation,
+    pub brain_output_activation: nn::Activation,
+    /// How `BrainType::FeedForward` brains are seeded; the default uniform
+    /// range saturates the hidden layer when `eye_cells` is large.
+    pub brain_initializer: nn::Initializer,
+    /// Recurrent hidden layers let animals remember food they lost sight of.
+    pub brain_kind: nn::LayerKind,
+    /// Lets `BrainType::FeedForward` brains keep learning during their
+    /// lifetime, with evolved per-connection coefficients.
+    pub brain_plasticity: Option<nn::Plasticity>,
+    /// Fed to plastic brains whenever their animal eats.
+    pub brain_reward: f32,
+    /// Chances of every connected input weight of a `BrainType::FeedForward`
+    /// offspring to be disconnected, and of every disconnected one to be
+    /// reconnected; sparsity drifts towards
+    /// `disconnect / (disconnect + connect)`. Elites are left as they are.
+    pub brain_disconnect_chance: f32,
+    pub brain_connect_chance: f32,
+    /// Keep every animal's `Animal::trace`, for drawing brains. Slower.
+    pub brain_traced: bool,
+    /// Integration step of `BrainType::Ctrnn` brains, per simulation step.
+    pub brain_dt: f32,
 
     pub speed_min: f32,
     pub speed_max: f32,
@@ -18,9 +44,27 @@ pub struct Config {
     pub count_animal: usize,
     pub count_food: usize,
 
+    /// Parameterised methods take their parameters inline, e.g.
+    /// `{"Tournament": 3}`.
     pub selection_method: nn::Selection,
+    /// Applied to the fitness before `selection_method`.
+    pub fitness_scaling: nn::Scaling,
     pub mutation_method: nn::Mutation,
+    /// `Neuron` and `Layer` need `BrainType::FeedForward`, and act like
+    /// `Uniform` otherwise.
     pub crossover_method: nn::Crossover,
+    /// Lets the parameters of the methods above change as generations go
+    /// by, e.g. `{"mutation_coeff": {"Linear": [0.3, 0.05, 200]}}`.
+    pub schedules: nn::Schedules,
+    /// How many of the fittest animals go on to the next generation
+    /// unchanged; ignored by `BrainType::Neat`, which keeps its own
+    /// champions.
+    pub elite_count: usize,
+    /// Whether elites earn their fitness anew, rather than keeping the one
+    /// they were picked for.
+    pub elite_reevaluate: bool,
+    /// Used instead of the methods above by `BrainType::Neat`.
+    pub neat: nn::NeatConfig,
 }
 
 impl Default for Config {
@@ -29,7 +73,18 @@ impl Default for Config {
             eye_fov: PI + FRAC_PI_2,
             eye_range: 0.25,
             eye_cells: 9,
+            brain_type: BrainType::FeedForward,
             brain_neurons: 9,
+            brain_activation: nn::Activation::ReLU,
+            brain_output_activation: nn::Activation::ReLU,
+            brain_initializer: nn::Initializer::default(),
+            brain_kind: nn::LayerKind::Dense,
+            brain_plasticity: None,
+            brain_reward: 1.0,
+            brain_disconnect_chance: 0.0,
+            brain_connect_chance: 0.0,
+            brain_traced: false,
+            brain_dt: 0.1,
             speed_min: 0.002,
             speed_max: 0.6,
             speed_accel: 0.2,
@@ -38,8 +93,44 @@ impl Default for Config {
             count_animal: 30,
             count_food: 100,
             selection_method: nn::Selection::Roulette,
-            mutation_method: nn::Mutation::Gaussian(0.01, 0.3),
+            fitness_scaling: nn::Scaling::Raw,
+            mutation_method: nn::Mutation::UniformPerturbation(0.01, 0.3),
             crossover_method: nn::Crossover::Uniform,
+            schedules: Default::default(),
+            elite_count: 0,
+            elite_reevaluate: true,
+            neat: Default::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// As saved before any of the `brain_*` fields existed.
+    #[test]
+    fn test_old_config() {
+        let json = r#"{
+            "eye_fov": 4.0,
+            "eye_range": 0.25,
+            "eye_cells": 9,
+            "brain_neurons": 9,
+            "speed_min": 0.002,
+            "speed_max": 0.6,
+            "speed_accel": 0.2,
+            "rot_accel": 1.5,
+            "gen_len": 3000,
+            "count_animal": 30,
+            "count_food": 100,
+            "selection_method": "Roulette",
+            "mutation_method": {"Gaussian": [0.01, 0.3]},
+            "crossover_method": "Uniform"
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+
+        assert_eq!(config.eye_fov, 4.0);
+        assert_eq!(config.brain_output_activation, nn::Activation::ReLU);
+        assert_eq!(config.elite_count, 0);
+    }
+}
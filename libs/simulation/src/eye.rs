@@ -33,6 +33,20 @@ impl Eye {
     ) -> DVector<f32> {
         let mut cells = vec![0.0; self.cells];
 
+        self.process_vision_into(pos, rot, food, &mut cells);
+
+        DVector::from_vec(cells)
+    }
+
+    /// Same as `process_vision`, but accumulates into caller-owned cells,
+    /// which must be zeroed and `self.cells()` long.
+    pub fn process_vision_into(
+        &self,
+        pos: Point2<f32>,
+        rot: Rotation2<f32>,
+        food: &[Food],
+        cells: &mut [f32],
+    ) {
         for food in food {
             let vec = food.pos - pos;
             let dist = vec.norm();
@@ -59,8 +73,6 @@ impl Eye {
 
             cells[cell] += energy;
         }
-
-        DVector::from_vec(cells)
     }
 }
 
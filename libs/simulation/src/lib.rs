@@ -17,15 +17,39 @@ pub struct Simulation {
     world: World,
     age: usize,
     config: Config,
+    ga: nn::GeneticAlgorithm,
+    neat: nn::Neat,
 }
 
 impl Simulation {
     pub fn random(rng: &mut dyn RngCore, config: Config) -> Self {
-        Self {
+        Self::try_random(rng, config).unwrap()
+    }
+
+    /// Fails on method parameters the genetic algorithm can't work with.
+    pub fn try_random(rng: &mut dyn RngCore, config: Config) -> Result<Self, nn::NetworkError> {
+        let mut ga = nn::GeneticAlgorithm::new(
+            config.selection_method,
+            config.crossover_method,
+            config.mutation_method,
+        )
+        .with_scaling(config.fitness_scaling)
+        .with_schedules(config.schedules.clone())
+        .with_elitism(config.elite_count, config.elite_reevaluate);
+
+        if let Some(layout) = Brain::layout(&config) {
+            ga = ga.with_layout(layout);
+        }
+
+        ga.check()?;
+
+        Ok(Self {
             world: World::random(rng, &config),
+            ga,
+            neat: nn::Neat::new(config.neat.clone(), config.eye_cells, 2),
             config,
             age: 0,
-        }
+        })
     }
 
     pub fn world(&self) -> &World {
@@ -38,11 +62,16 @@ impl Simulation {
 
     pub fn step(&mut self, rng: &mut dyn RngCore) -> Option<nn::Statistics> {
         self.process_collisions(rng);
+
         self.world.animals.par_iter_mut().for_each(|animal| {
             animal.process_brain(&self.world.food, &self.config);
-            animal.process_movement();
         });
 
+        self.world
+            .animals
+            .par_iter_mut()
+            .for_each(|animal| animal.process_movement());
+
         self.age += 1;
 
         if self.age > self.config.gen_len {
@@ -67,6 +96,7 @@ impl Simulation {
 
                 if dist <= 0.02 {
                     animal.collisions += 1;
+                    animal.reward(self.config.brain_reward);
                     food.pos = rng.gen();
                 }
             }
@@ -76,6 +106,21 @@ impl Simulation {
     fn evolve(&mut self, rng: &mut dyn RngCore) -> nn::Statistics {
         self.age = 0;
 
+        let (animals, stats) = match self.config.brain_type {
+            BrainType::Neat => self.evolve_neat(rng),
+            _ => self.evolve_ga(rng),
+        };
+
+        self.world.animals = animals;
+
+        for food in &mut self.world.food {
+            food.pos = rng.gen();
+        }
+
+        stats
+    }
+
+    fn evolve_ga(&mut self, rng: &mut dyn RngCore) -> (Vec<Animal>, nn::Statistics) {
         let current_pop: Vec<AnimalIndividual> = self
             .world
             .animals
@@ -83,23 +128,113 @@ impl Simulation {
             .map(AnimalIndividual::from_animal)
             .collect();
 
-        let ga = nn::GeneticAlgorithm::new(
-            self.config.selection_method,
-            self.config.crossover_method,
-            self.config.mutation_method,
-        );
+        let (evolved_pop, stats) = self.ga.evolve(rng, &current_pop);
+
+        let animals = evolved_pop
+            .into_iter()
+            .map(|indiv| indiv.into_animal(rng, &self.config))
+            .collect();
+
+        (animals, stats)
+    }
 
-        let (evolved_pop, stats) = ga.evolve(rng, &current_pop);
+    fn evolve_neat(&mut self, rng: &mut dyn RngCore) -> (Vec<Animal>, nn::Statistics) {
+        let current_pop: Vec<AnimalNeatIndividual> = self
+            .world
+            .animals
+            .iter()
+            .map(AnimalNeatIndividual::from_animal)
+            .collect();
 
-        self.world.animals = evolved_pop
+        let (evolved_pop, stats) = self.neat.evolve(rng, &current_pop);
+
+        let animals = evolved_pop
             .into_iter()
             .map(|indiv| indiv.into_animal(rng, &self.config))
             .collect();
 
-        for food in &mut self.world.food {
-            food.pos = rng.gen();
+        (animals, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn config() -> Config {
+        Config {
+            gen_len: 5,
+            count_animal: 6,
+            count_food: 10,
+            ..Default::default()
         }
+    }
 
-        stats
+    #[test]
+    fn test_structured_crossover_without_layout() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+
+        for crossover_method in [nn::Crossover::Neuron, nn::Crossover::Layer] {
+            let config = Config {
+                brain_type: BrainType::Ctrnn,
+                crossover_method,
+                ..config()
+            };
+            let mut simulation = Simulation::random(&mut rng, config);
+
+            simulation.train(&mut rng);
+            simulation.train(&mut rng);
+
+            assert_eq!(simulation.world().animals().len(), 6);
+        }
+    }
+
+    #[test]
+    fn test_sparsity_survives_evolve() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            brain_disconnect_chance: 0.1,
+            ..config()
+        };
+        let mut simulation = Simulation::random(&mut rng, config);
+        let sparsity = |simulation: &Simulation| -> Vec<f32> {
+            simulation
+                .world()
+                .animals()
+                .iter()
+                .map(|animal| animal.brain.nn.as_network().unwrap().sparsity())
+                .collect()
+        };
+
+        simulation.train(&mut rng);
+
+        let before = sparsity(&simulation);
+
+        simulation.train(&mut rng);
+
+        let after = sparsity(&simulation);
+        let min = before.iter().copied().fold(f32::INFINITY, f32::min);
+
+        // Nothing reconnects, so every child is at least as sparse as the
+        // parent it took its connections from.
+        assert!(min > 0.0);
+        assert!(after.iter().all(|&sparsity| sparsity >= min));
+        assert!(after.iter().sum::<f32>() > before.iter().sum::<f32>());
+    }
+
+    #[test]
+    fn test_invalid_methods() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let config = Config {
+            selection_method: nn::Selection::Tournament(0),
+            ..config()
+        };
+
+        assert!(matches!(
+            Simulation::try_random(&mut rng, config),
+            Err(nn::NetworkError::InvalidSelection(_))
+        ));
     }
 }
@@ -12,6 +12,11 @@ viewport.style.height = height + 'px';
 const context = viewport.getContext('2d');
 context.scale(scale, scale);
 
+// Unit variants serialize as their name, the rest as `{ Name: params }`.
+const methodName = (method) =>
+	typeof method === 'string' ? method : Object.keys(method)[0];
+const config = simulation.config();
+
 const selection = simulation.selection_methods();
 let select = document.getElementById('selectionMethod');
 
@@ -23,6 +28,8 @@ for (const i in selection) {
 	select.add(opt, 0);
 }
 
+select.value = methodName(config.selection_method);
+
 const mutation = simulation.mutation_methods();
 select = document.getElementById('mutationMethod');
 
@@ -33,6 +40,8 @@ for (const i in mutation) {
 	select.add(opt, 0);
 }
 
+select.value = methodName(config.mutation_method);
+
 const crossover = simulation.crossover_methods();
 select = document.getElementById('crossoverMethod');
 
@@ -43,7 +52,9 @@ for (const i in crossover) {
 	select.add(opt, 0);
 }
 
-CanvasRenderingContext2D.prototype.drawTriangle = function (x, y, size, rot) {
+select.value = methodName(config.crossover_method);
+
+CanvasRenderingContext2D.prototype.drawTriangle = function (x, y, size, rot, color) {
 	this.beginPath();
 	this.moveTo(x - Math.sin(rot) * size * 1.5, y + Math.cos(rot) * size * 1.5);
 	this.lineTo(
@@ -55,7 +66,7 @@ CanvasRenderingContext2D.prototype.drawTriangle = function (x, y, size, rot) {
 		y + Math.cos(rot + (4.0 / 3.0) * Math.PI) * size
 	);
 	this.lineTo(x - Math.sin(rot) * size * 1.5, y + Math.cos(rot) * size * 1.5);
-	this.fillStyle = '#0E8388';
+	this.fillStyle = color;
 	this.strokeStyle = '#DDDDDD';
 	this.stroke();
 	this.fill();
@@ -69,10 +80,23 @@ CanvasRenderingContext2D.prototype.drawCircle = function (x, y, radius) {
 };
 
 document.getElementById('train').onclick = function () {
-	let stats = simulation.train();
-	const fitness = document.getElementById("fitness");
+	const stats = simulation.train();
+	const fitness = document.getElementById('fitness');
+	const lines = [
+		`Min. Fitness = ${stats.min_fitness.toFixed(2)}`,
+		`Max. Fitness = ${stats.max_fitness.toFixed(2)}`,
+		`Avg. Fitness = ${stats.avg_fitness.toFixed(2)}`,
+		`Median Fitness = ${stats.median_fitness.toFixed(2)}`,
+		`Std. Dev. = ${stats.stddev_fitness.toFixed(2)}`,
+		`Quartiles = ${stats.lower_quartile_fitness.toFixed(2)} / ${stats.upper_quartile_fitness.toFixed(2)}`,
+	];
+
+	// NEAT brains don't have a fixed genotype to compare.
+	if (stats.diversity) {
+		lines.push(`Diversity = ${stats.diversity.mean_distance.toFixed(2)}`);
+	}
 
-	fitness.innerHTML = stats;
+	fitness.innerHTML = lines.join('</br>');
 };
 
 document.getElementById('submit').onclick = function () {
@@ -86,6 +110,8 @@ document.getElementById('submit').onclick = function () {
 	const actCount = parseInt(document.getElementById('actCount').value);
 	const pntCount = parseInt(document.getElementById('pntCount').value);
 	const selectionMethod = document.getElementById('selectionMethod').value;
+	const crossoverMethod = document.getElementById('crossoverMethod').value;
+	const mutationMethod = document.getElementById('mutationMethod').value;
 
 	conf.brain_neurons = neurons;
 	conf.speed_min = minSpeed;
@@ -94,7 +120,9 @@ document.getElementById('submit').onclick = function () {
 	conf.gen_len = genLen;
 	conf.count_animal = actCount;
 	conf.count_food = pntCount;
-	conf.selection_method = selectionMethod;
+	conf.selection_method = sim.Simulation.selection_method(selectionMethod);
+	conf.crossover_method = sim.Simulation.crossover_method(crossoverMethod);
+	conf.mutation_method = sim.Simulation.mutation_method(mutationMethod);
 
 	simulation = new sim.Simulation(conf);
 };
@@ -119,7 +147,9 @@ function redraw() {
 			animal.x * width,
 			animal.y * height,
 			0.01 * width,
-			animal.rot
+			animal.rot,
+			// Elites carried over unchanged from the previous generation.
+			animal.elite ? '#F2CD5C' : '#0E8388'
 		);
 	}
 